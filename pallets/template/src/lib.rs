@@ -16,7 +16,7 @@ mod tests;
 mod benchmarking;
 
 mod types;
-pub use types::{PropertyId, Property, Listing, ListingId, Tenancy, TenancyId, Offer, OfferId, OfferStatus};
+pub use types::{PropertyId, Property, Listing, ListingId, Tenancy, TenancyId, Offer, OfferId, OfferStatus, CounterOffer, CounterOfferStatus, OfferMode, AcceptancePolicy, SealedBid, ListingStatus, ListingAttributes, Furnishing, ListingFilter, ViewingSlot, AgentAssignment, AgentPermissions, AgentPermission, RegistrationRequest, RegistrationRequestId};
 
 
 use frame_support::traits::fungible;
@@ -29,9 +29,11 @@ pub mod pallet {
 	use super::*;
 	use frame_support::pallet_prelude::*;
 	use frame_system::pallet_prelude::*;
-	use frame_support::traits::{fungible, fungible::{MutateFreeze, Inspect as OtherInspect, Mutate}};
+	use frame_support::traits::{fungible, fungible::{MutateHold, InspectHold}};
 	use frame_support::dispatch::RawOrigin;
-	use frame_support::traits::tokens::Preservation::Preserve;
+	use frame_support::traits::tokens::{Fortitude, Precision, Restriction};
+	use frame_support::sp_runtime::traits::{Hash, One, Saturating, Zero};
+	use frame_support::sp_runtime::Permill;
 
 	#[pallet::pallet]
 	pub struct Pallet<T>(_);
@@ -46,21 +48,57 @@ pub mod pallet {
 		type MaxNumberOfAgents: Get<u32>;
 		type MaxOffersPerListing: Get<u32>;
 		type MaxOffersPerApplicant: Get<u32>;
+		type MaxOffersExpiringPerBlock: Get<u32>;
+		type MaxCounterOffers: Get<u32>;
+		type MaxListingDeadlinesPerBlock: Get<u32>;
+		type MaxViewingSlotsPerListing: Get<u32>;
+		/// How many offers an acceptance policy tries to settle on a listing in a single block.
+		/// MaxListingDeadlinesPerBlock times this many settlements have to fit in on_initialize.
+		type MaxAcceptanceAttempts: Get<u32>;
+		/// How long after a tenancy ends the landlord has to settle the security deposit,
+		/// after which the lead tenant can end the tenancy and take the whole deposit back.
+		type DepositReturnPeriod: Get<BlockNumberFor<Self>>;
+
+		/// Held from the lister for as long as a listing is on the market, pays for the listing
+		/// and its entries in the listing indexes.
+		type ListingDeposit: Get<BalanceOf<Self>>;
+		/// Held from the lead tenant for as long as an offer is pending, pays for the offer
+		/// and the slots it takes in ListingOffers, ApplicantOffers and OfferExpiries.
+		type OfferDeposit: Get<BalanceOf<Self>>;
+
+		/// Approves or rejects landlords' requests to register a property.
+		type RegistrarOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+		/// Held from the landlord while their registration request is pending, slashed if the
+		/// registrar rejects the request as fraudulent.
+		type RegistrationDeposit: Get<BalanceOf<Self>>;
+		type MaxPendingRegistrations: Get<u32>;
 
 		/// Type to access the Balances Pallet.
+		/// Its MaxHolds has to be at least the number of HoldReason variants.
 		type NativeBalance: fungible::Inspect<Self::AccountId>
 			+ fungible::Mutate<Self::AccountId>
-			+ fungible::hold::Inspect<Self::AccountId>
-			+ fungible::hold::Mutate<Self::AccountId>
-			+ fungible::freeze::Inspect<Self::AccountId, Id = Self::RuntimeFreezeReason>
-			+ fungible::freeze::Mutate<Self::AccountId>;
+			+ fungible::hold::Inspect<Self::AccountId, Reason = Self::RuntimeHoldReason>
+			+ fungible::hold::Mutate<Self::AccountId>;
 
-		type RuntimeFreezeReason: From<FreezeReason>;
+		type RuntimeHoldReason: From<HoldReason>;
 	}
 
+	// Holds are shared by every offer and listing an account has, so an account takes at most one hold
+	// per reason however many it has open. What each offer or listing put on hold is kept on its record.
 	#[pallet::composite_enum]
-	pub enum FreezeReason {
-		Offer(OfferId),
+	pub enum HoldReason {
+		// Tenants' rent shares, see Offer::rent_shares
+		Offer,
+		// See SealedBid::max_price
+		SealedBid,
+		HoldingDeposit,
+		SecurityDeposit,
+		// See Listing::deposit
+		ListingDeposit,
+		// See Offer::deposit
+		OfferDeposit,
+		// See RegistrationRequest::deposit
+		RegistrationDeposit,
 	}
 
 	#[pallet::storage]
@@ -80,8 +118,56 @@ pub mod pallet {
 	pub type PropertyCounter<T: Config> = StorageValue<_, PropertyId>;
 
 	#[pallet::storage]
-	// Property listings
-	pub type Listings<T: Config> = StorageMap<_, Blake2_128Concat, PropertyId, Listing<T>>;
+	// Requests from landlords to register a property, waiting on the registrar
+	pub type RegistrationRequests<T: Config> = StorageMap<_, Blake2_128Concat, RegistrationRequestId, RegistrationRequest<T>>;
+
+	#[pallet::storage]
+	// Pending registration requests, oldest first
+	pub type PendingRegistrations<T: Config> = StorageValue<_, BoundedVec<RegistrationRequestId, T::MaxPendingRegistrations>, ValueQuery>;
+
+	#[pallet::storage]
+	// Used to generate new registration request id's
+	pub type RegistrationRequestCounter<T: Config> = StorageValue<_, RegistrationRequestId>;
+
+	#[pallet::storage]
+	// Properties by the hash of their (address, postal_code), so a property can only be registered once
+	pub type PropertyKeys<T: Config> = StorageMap<_, Blake2_128Concat, T::Hash, PropertyId>;
+
+	#[pallet::storage]
+	// Property listings, closed and let listings are kept for the property's rent history
+	pub type Listings<T: Config> = StorageMap<_, Blake2_128Concat, ListingId, Listing<T>>;
+
+	#[pallet::storage]
+	// Every listing a property has had
+	pub type PropertyListings<T: Config> = StorageDoubleMap<_, Blake2_128Concat, PropertyId, Blake2_128Concat, ListingId, ()>;
+
+	#[pallet::storage]
+	// The listing a property currently has on the market, a property can only have one at a time
+	pub type PropertyOpenListing<T: Config> = StorageMap<_, Blake2_128Concat, PropertyId, ListingId>;
+
+	#[pallet::storage]
+	// Viewing slots published for a listing, a slot is referred to by its index
+	pub type ViewingSlots<T: Config> = StorageMap<_, Blake2_128Concat, ListingId, BoundedVec<ViewingSlot<T>, T::MaxViewingSlotsPerListing>>;
+
+	#[pallet::storage]
+	// Applicants who have been marked as attending a viewing of a listing
+	pub type ViewingAttendance<T: Config> = StorageDoubleMap<_, Blake2_128Concat, ListingId, Blake2_128Concat, T::AccountId, ()>;
+
+	#[pallet::storage]
+	// Listings with a deadline that the pallet acts on at the start of the block, e.g. the end of a reveal window
+	pub type ListingDeadlines<T: Config> = StorageMap<_, Blake2_128Concat, BlockNumberFor<T>, BoundedVec<ListingId, T::MaxListingDeadlinesPerBlock>>;
+
+	#[pallet::storage]
+	// Hidden offers committed on sealed listings that have not been revealed yet
+	pub type SealedBids<T: Config> = StorageDoubleMap<_, Blake2_128Concat, ListingId, Blake2_128Concat, T::AccountId, SealedBid<T>>;
+
+	#[pallet::storage]
+	// Applicants with an unrevealed commitment on a sealed listing
+	pub type ListingSealedBidders<T: Config> = StorageMap<_, Blake2_128Concat, ListingId, BoundedVec<T::AccountId, T::MaxOffersPerListing>>;
+
+	#[pallet::storage]
+	// The offer currently winning an auction listing
+	pub type AuctionLeaders<T: Config> = StorageMap<_, Blake2_128Concat, ListingId, OfferId>;
 
 	#[pallet::storage]
 	// Used to generate new listing id's
@@ -91,9 +177,12 @@ pub mod pallet {
 	// Offers on properties
 	pub type Offers<T: Config> = StorageMap<_, Blake2_128Concat, OfferId, Offer<T>>;
 
+	#[pallet::storage]
+	// The offer an applicant has open on a listing, an applicant can only have one per listing
+	pub type ApplicantListingOffers<T: Config> = StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, ListingId, OfferId>;
+
 	#[pallet::storage]
 	// Offers on listings by applicant
-	// pub type ApplicantOffers<T: Config> = StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, ListingId, OfferId>;
 	pub type ApplicantOffers<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, BoundedVec<OfferId, T::MaxOffersPerApplicant>>;
 
 	#[pallet::storage]
@@ -104,6 +193,14 @@ pub mod pallet {
 	// Used to generate new offer id's
 	pub type OfferCounter<T: Config> = StorageValue<_, OfferId>;
 
+	#[pallet::storage]
+	// Every round of counter offers made by the landlord on an offer, oldest first
+	pub type OfferNegotiations<T: Config> = StorageMap<_, Blake2_128Concat, OfferId, BoundedVec<CounterOffer<T>, T::MaxCounterOffers>>;
+
+	#[pallet::storage]
+	// Offers to expire at the start of a block, i.e. the block after their valid_until
+	pub type OfferExpiries<T: Config> = StorageMap<_, Blake2_128Concat, BlockNumberFor<T>, BoundedVec<OfferId, T::MaxOffersExpiringPerBlock>>;
+
 
 	#[pallet::storage]
 	// A structure to hold information about tenancies
@@ -118,15 +215,56 @@ pub mod pallet {
 		NewApplicantRegistered { applicant_id: T::AccountId },
 		NewLandlordRegistered { landlord_id: T::AccountId },
 		NewPropertyRegistered { address: T::Hash, postal_code: T::Hash },
-		NewListingCreated {property_id: PropertyId, rental_price: u32, availability_date:BlockNumberFor<T>},
+		PropertyRegistrationRequested {request_id: RegistrationRequestId, landlord_id: T::AccountId, address: T::Hash, postal_code: T::Hash, evidence: T::Hash},
+		PropertyRegistrationApproved {request_id: RegistrationRequestId, property_id: PropertyId},
+		PropertyRegistrationRejected {request_id: RegistrationRequestId, deposit_slashed: bool},
+		NewListingCreated {listing_id: ListingId, property_id: PropertyId, rental_price: u32, availability_date:BlockNumberFor<T>, holding_deposit: u32, security_deposit: u32},
 		NewOfferSubmitted {listing_id: ListingId, offer_price: u32, offer_start_date: BlockNumberFor<T>, offer_end_date: BlockNumberFor<T>, prospective_tenant_ids: BoundedVec<T::AccountId, T::MaxNumberOfTenants>},
 		OfferAccepted {offer_id: OfferId},
 		ApplicantSignedOffer {applicant_id: T::AccountId},
+		OfferWithdrawn {offer_id: OfferId, withdrawn_by: T::AccountId},
+		OfferRejected {offer_id: OfferId, reason_code: Option<u8>},
+		CompetingOfferRejected {offer_id: OfferId, accepted_offer_id: OfferId},
+		OfferExpired {offer_id: OfferId},
+		OfferDeclined {offer_id: OfferId, applicant_id: T::AccountId},
+		ApplicantRevokedSignature {offer_id: OfferId, applicant_id: T::AccountId},
+		SealedOfferCommitted {listing_id: ListingId, applicant_id: T::AccountId},
+		SealedOfferRevealed {listing_id: ListingId, offer_id: OfferId},
+		SealedOfferExpired {listing_id: ListingId, applicant_id: T::AccountId},
+		OfferAutoAccepted {listing_id: ListingId, offer_id: OfferId},
+		NoOfferAutoAccepted {listing_id: ListingId},
+		ListingUpdated {listing_id: ListingId, rental_price: u32, availability_date: BlockNumberFor<T>},
+		ListingClosed {listing_id: ListingId},
+		AgentAssigned {property_id: PropertyId, agent_id: T::AccountId, permissions: AgentPermissions, commission: Permill},
+		AgentRemoved {property_id: PropertyId, agent_id: T::AccountId},
+		BidPlaced {listing_id: ListingId, offer_id: OfferId, bidder: T::AccountId, bid_price: u32},
+		Outbid {listing_id: ListingId, offer_id: OfferId},
+		AuctionSettled {listing_id: ListingId, offer_id: OfferId},
+		AuctionEndedWithoutWinner {listing_id: ListingId},
+		ViewingSlotsPublished {listing_id: ListingId, number_of_slots: u32},
+		ViewingBooked {listing_id: ListingId, slot_index: u32, applicant_id: T::AccountId},
+		ViewingCancelled {listing_id: ListingId, slot_index: u32, applicant_id: T::AccountId},
+		ViewingAttended {listing_id: ListingId, slot_index: u32, applicant_id: T::AccountId},
+		RentPaid {offer_id: OfferId, from: T::AccountId, to: T::AccountId, amount: u32},
+		CommissionPaid {offer_id: OfferId, from: T::AccountId, to: T::AccountId, amount: u32},
+		HoldingDepositReturned {offer_id: OfferId, to: T::AccountId, amount: u32},
+		SecurityDepositRetained {offer_id: OfferId, from: T::AccountId, amount: u32},
+		TenancyEnded {property_id: PropertyId, offer_id: OfferId, deposit_returned: u32, deposit_retained: u32},
+		OfferAmended {offer_id: OfferId, offer_price: u32, offer_start_date: BlockNumberFor<T>, offer_end_date: BlockNumberFor<T>, prospective_tenant_ids: BoundedVec<T::AccountId, T::MaxNumberOfTenants>},
+		CounterOfferProposed {offer_id: OfferId, round: u32, offer_price: u32, offer_start_date: BlockNumberFor<T>, offer_end_date: BlockNumberFor<T>},
+		CounterOfferAccepted {offer_id: OfferId, round: u32},
+		CounterOfferDeclined {offer_id: OfferId, round: u32, declined_by: T::AccountId},
 	}
 
 	#[pallet::error]
 	pub enum Error<T> {
 		TooManyProperties,
+		PropertyAlreadyRegistered,
+		RegistrationAlreadyRequested,
+		RegistrationRequestDoesNotExist,
+		TooManyPendingRegistrations,
+		TooManyRegistrationRequests,
+		InsufficientFundsForRegistrationDeposit,
 		TooManyListings,
 		TooManyOffers,
 		LandlordNotVerified,
@@ -147,6 +285,82 @@ pub mod pallet {
 		OfferCannotBeAccepted,
 		TooManyTenants,
 		OfferNotFullySigned,
+		OfferNotPending,
+		AlreadyOfferedOnListing,
+		// The lead tenant should withdraw the offer instead
+		LeadTenantCannotDecline,
+		OfferNotSigned,
+		OfferAlreadySigned,
+		// The landlord, one of their agents or the agent who listed the property is one of the tenants on the offer
+		LandlordCannotOfferOnOwnProperty,
+		LeadTenantMustBeProspectiveTenant,
+		DuplicateTenantIds,
+		RentSharesDoNotMatchTenants,
+		RentSharesMustSumToOfferPrice,
+		InsufficientFundsForRentShare,
+		InsufficientFundsForDeposits,
+		TooManyOffersExpiringInBlock,
+		TooManyCounterOffers,
+		CounterOfferAlreadyOpen,
+		NoOpenCounterOffer,
+		InvalidSealedBidWindow,
+		TooManyListingDeadlinesInBlock,
+		// Offers on a sealed listing have to be committed and revealed
+		ListingIsSealed,
+		ListingIsNotSealed,
+		CommitWindowClosed,
+		NotInRevealWindow,
+		// Sealed offers can only be accepted once every applicant had the chance to reveal
+		RevealWindowOpen,
+		SealedBidDoesNotExist,
+		CommitmentMismatch,
+		OfferAboveSealedMaximum,
+		InvalidAcceptancePolicy,
+		RentShareNotHeld,
+		RentTransferFailed,
+		HoldingDepositReleaseFailed,
+		SecurityDepositNotHeld,
+		ListingNotOpen,
+		PropertyAlreadyListed,
+		InvalidListingAttributes,
+		TooManyOccupants,
+		TermTooShort,
+		TermTooLong,
+		InsufficientFundsForStorageDeposit,
+		TooManyViewingSlots,
+		InvalidViewingSlot,
+		ViewingSlotDoesNotExist,
+		ViewingSlotAlreadyBooked,
+		ViewingAlreadyBooked,
+		ViewingSlotNotBooked,
+		ViewingAlreadyStarted,
+		ViewingNotStarted,
+		ViewingNotAttended,
+		ListingIsAuction,
+		ListingIsNotAuction,
+		InvalidAuction,
+		AuctionEnded,
+		AuctionNotEnded,
+		BidBelowReserve,
+		BidTooLow,
+		BidNotFullySigned,
+		LeadingBidIsBinding,
+		TooManyAgents,
+		AgentNotAssigned,
+		LandlordCannotBeAgent,
+		TenancyDoesNotExist,
+		TenancyNotEnded,
+		// The landlord can keep at most the security deposit, the lead tenant can't keep any of it
+		RetainedDepositTooHigh,
+		DepositReturnPeriodNotOver,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(block_number: BlockNumberFor<T>) -> Weight {
+			Self::expire_offers(block_number)
+				.saturating_add(Self::process_listing_deadlines(block_number))
+		}
 	}
 
 	#[pallet::call]
@@ -172,139 +386,110 @@ pub mod pallet {
 		}
 
 		#[pallet::call_index(2)]
-		#[pallet::weight(10_000 + T::DbWeight::get().writes(1).ref_time())]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 3).ref_time())]
 		pub fn register_property(origin: OriginFor<T>, address: T::Hash, postal_code: T::Hash, landlord_id: T::AccountId ) -> DispatchResult {
 			ensure_root(origin)?;
-
-			let property_count = PropertyCounter::<T>::get().unwrap_or_default();
-			ensure!(property_count.checked_add(1).is_some(), Error::<T>::TooManyProperties);
-			let new_property_count = property_count + 1;
-			// The id should actually be a combination of the address, postal code and landlord id hashed
-			let new_property = Property::new(new_property_count, landlord_id, address, postal_code);
-			
-			Properties::<T>::insert(&new_property_count, new_property);
-
-			Self::deposit_event(Event::NewPropertyRegistered { address, postal_code });
+			Self::do_register_property(address, postal_code, landlord_id)?;
 			Ok(())
 		}
 
 		#[pallet::call_index(3)]
-		#[pallet::weight(10_000 + T::DbWeight::get().writes(1).ref_time())]
-		pub fn create_listing(origin: OriginFor<T>, property_id: PropertyId, rental_price: u32, availability_date: BlockNumberFor<T>) -> DispatchResult {
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 5).ref_time())]
+		pub fn create_listing(origin: OriginFor<T>, property_id: PropertyId, rental_price: u32, availability_date: BlockNumberFor<T>, holding_deposit: u32, security_deposit: u32, offer_mode: OfferMode<BlockNumberFor<T>>, acceptance_policy: AcceptancePolicy<BlockNumberFor<T>>, attributes: ListingAttributes<BlockNumberFor<T>>) -> DispatchResult {
 			// Only landlords and their agents should be able to list properties
-			let landlord_id = ensure_signed(origin)?;
+			let lister = ensure_signed(origin)?;
 			ensure!(Properties::<T>::contains_key(&property_id), Error::<T>::PropertyDoesNotExist);
 
 			let property = Properties::<T>::get(property_id).unwrap();
-			ensure!(property.landlord_id == landlord_id, Error::<T>::Unauthorized);
+			ensure!(property.is_authorised(&lister, AgentPermission::ManageListings), Error::<T>::Unauthorized);
+			ensure!(!PropertyOpenListing::<T>::contains_key(&property_id), Error::<T>::PropertyAlreadyListed);
+			// Offers on a let property could never be accepted, it can be listed again once its tenancy is ended
+			ensure!(!Tenancies::<T>::contains_key(&property_id), Error::<T>::TenancyAlreadyExists);
 
 			let listing_count = ListingCounter::<T>::get().unwrap_or_default();
 			ensure!(listing_count.checked_add(1).is_some(), Error::<T>::TooManyListings);
 
 			let new_listing_id = listing_count + 1;
-			let new_listing = property.create_listing(new_listing_id, rental_price, availability_date, landlord_id);
+			let current_block_number =  frame_system::Pallet::<T>::block_number();
+			ensure!(attributes.max_occupants != Some(0), Error::<T>::InvalidListingAttributes);
+			if let (Some(min_term), Some(max_term)) = (attributes.min_term, attributes.max_term) {
+				ensure!(min_term <= max_term, Error::<T>::InvalidListingAttributes);
+			}
+			if let OfferMode::Sealed { commit_end, reveal_end } = offer_mode {
+				ensure!(commit_end > current_block_number && reveal_end > commit_end, Error::<T>::InvalidSealedBidWindow);
+				// Unrevealed commitments are cleaned up the block after the reveal window closes
+				Self::schedule_listing_deadline(new_listing_id, reveal_end.saturating_add(One::one()))?;
+			}
+			if let OfferMode::Auction { end, .. } = offer_mode {
+				ensure!(end > current_block_number, Error::<T>::InvalidAuction);
+				// The winning bid is settled the block after the auction ends
+				Self::schedule_listing_deadline(new_listing_id, end.saturating_add(One::one()))?;
+			}
+			match (&offer_mode, &acceptance_policy) {
+				// Auctions pick their own winner
+				(OfferMode::Auction { .. }, AcceptancePolicy::Manual) => {},
+				(OfferMode::Auction { .. }, _) => return Err(Error::<T>::InvalidAcceptancePolicy.into()),
+				// Sealed offers can't be accepted before the reveal window closes
+				(OfferMode::Sealed { .. }, AcceptancePolicy::FirstAtAsking) => return Err(Error::<T>::InvalidAcceptancePolicy.into()),
+				(OfferMode::Sealed { reveal_end, .. }, AcceptancePolicy::HighestAfterDeadline { deadline }) => ensure!(deadline >= reveal_end, Error::<T>::InvalidAcceptancePolicy),
+				_ => {},
+			}
+			if let AcceptancePolicy::HighestAfterDeadline { deadline } = acceptance_policy {
+				ensure!(deadline > current_block_number, Error::<T>::InvalidAcceptancePolicy);
+				Self::schedule_listing_deadline(new_listing_id, deadline.saturating_add(One::one()))?;
+			}
+			let deposit = T::ListingDeposit::get();
+			let new_listing = property.create_listing(new_listing_id, rental_price, availability_date, holding_deposit, security_deposit, lister.clone(), deposit, offer_mode, acceptance_policy, attributes);
 			
+			Self::hold_storage_deposit(&HoldReason::ListingDeposit.into(), &lister, deposit)?;
 			Listings::<T>::insert(new_listing_id, new_listing);
+			PropertyListings::<T>::insert(&property_id, &new_listing_id, ());
+			PropertyOpenListing::<T>::insert(&property_id, new_listing_id);
+			ListingCounter::<T>::put(new_listing_id);
 
-			Self::deposit_event(Event::NewListingCreated { property_id, rental_price, availability_date });
+			Self::deposit_event(Event::NewListingCreated { listing_id: new_listing_id, property_id, rental_price, availability_date, holding_deposit, security_deposit });
 			Ok(())
 		}
 
 		#[pallet::call_index(4)]
-		#[pallet::weight(10_000 + T::DbWeight::get().writes(1).ref_time())]
-		pub fn submit_offer(origin: OriginFor<T>, listing_id: ListingId, offer_price: u32, offer_start_date: BlockNumberFor<T>, offer_end_date: BlockNumberFor<T>, prospective_tenant_ids: BoundedVec<T::AccountId, T::MaxNumberOfTenants>, valid_until: BlockNumberFor<T>) -> DispatchResult {
+		// Every tenant has to be verified, the lead tenant's rent share and the deposits take four holds
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(
+			20 + T::MaxNumberOfTenants::get() as u64,
+			16,
+		).ref_time())]
+		pub fn submit_offer(origin: OriginFor<T>, listing_id: ListingId, offer_price: u32, offer_start_date: BlockNumberFor<T>, offer_end_date: BlockNumberFor<T>, prospective_tenant_ids: BoundedVec<T::AccountId, T::MaxNumberOfTenants>, valid_until: BlockNumberFor<T>, custom_shares: Option<BoundedVec<u32, T::MaxNumberOfTenants>>) -> DispatchResult {
 			let applicant_id = ensure_signed(origin)?;
 			ensure!(VerifiedApplicants::<T>::contains_key(&applicant_id), Error::<T>::Unauthorized);
 			ensure!(Listings::<T>::contains_key(&listing_id), Error::<T>::ListingDoesNotExist);
-			ensure!(T::NativeBalance::total_balance(&applicant_id) >= offer_price.into(), Error::<T>::InsufficientFundsForOffer);
 			let offer_listing = Listings::<T>::get(&listing_id).unwrap();
-			let current_block_number =  frame_system::Pallet::<T>::block_number();
-			ensure!(valid_until > current_block_number, Error::<T>::OfferValidUntilMustBeFuture); // Maybe add min? Don't want one block offers
-			ensure!(offer_start_date >= current_block_number
-					&& offer_start_date < offer_end_date
-					&& offer_start_date >= offer_listing.availability_date, Error::<T>::InvalidOfferStartDate);
-			
-			ensure!(prospective_tenant_ids.len() > 0, Error::<T>::TenantsIdsCannotBeEmpty);
-			// ensure!(prospective_tenant_ids.len() <= T::MaxNumberOfTenants::get(), Error::<T>::TooManyTenants); Not necessary?
-			ensure!(&prospective_tenant_ids.iter().all(|applicant_id| VerifiedApplicants::<T>::contains_key(&applicant_id)), Error::<T>::AllApplicantsMustBeVerified);
-			let offer_count = OfferCounter::<T>::get().unwrap_or_default();
-			ensure!(offer_count.checked_add(1).is_some(), Error::<T>::TooManyOffers); // change to storage overflow
-			let new_offer_id = offer_count + 1;
-
-			let number_of_prospective_tenants = prospective_tenant_ids.len();
-			let init_ids_and_sigs: Vec<(T::AccountId, bool)> = prospective_tenant_ids.clone().into_iter().map(|t_id| if number_of_prospective_tenants == 1 {(t_id, true)} else {if t_id == applicant_id {(t_id, true)} else {(t_id, false)}}).collect();
-			let prospective_tenant_signatures = BoundedVec::try_from(init_ids_and_sigs).map_err(|_| Error::<T>::TooManyTenants)?; // should not be possible to err here
-			let all_signed = if number_of_prospective_tenants == 1 { true } else { false };
-			let new_offer = Offer::new(new_offer_id, offer_listing.property_id, offer_price, offer_start_date, offer_end_date, applicant_id.clone(), prospective_tenant_ids.clone(), prospective_tenant_signatures, valid_until, all_signed);
-			// new_offer.clone() does not work??
-			// let new_offer2 = Offer::new(new_offer_id, offer_listing.property_id, offer_price, offer_start_date, offer_end_date, prospective_tenant_ids.clone());
-			// We should prevent people from making multiple offers on a property.
-			let mut offers_on_listing = ListingOffers::<T>::get(&listing_id).unwrap_or(BoundedVec::new());
-			offers_on_listing.try_push(new_offer_id).map_err(|_| Error::<T>::TooManyOffersOnListing)?;
-
-			let mut all_applicant_offers = ApplicantOffers::<T>::get(&applicant_id).unwrap_or(BoundedVec::new());
-			all_applicant_offers.try_push(new_offer_id).map_err(|_| Error::<T>::MaxOffersForApplicantReached)?;
+			// Sealed listings only take offers through commit_sealed_offer and reveal_sealed_offer,
+			// auction listings through place_bid
+			match offer_listing.offer_mode {
+				OfferMode::Open => {},
+				OfferMode::Sealed { .. } => return Err(Error::<T>::ListingIsSealed.into()),
+				OfferMode::Auction { .. } => return Err(Error::<T>::ListingIsAuction.into()),
+			}
 
-
-			ListingOffers::<T>::insert(&listing_id, &offers_on_listing);
-			ApplicantOffers::<T>::insert(&applicant_id, &all_applicant_offers);
-			Offers::<T>::insert(&new_offer_id, &new_offer);
-
-			T::NativeBalance::set_freeze(
-				&FreezeReason::Offer(new_offer_id).into(),
-				&applicant_id,
-				offer_price.into(),
-			);
-
-			Self::deposit_event(Event::NewOfferSubmitted { listing_id, offer_price, offer_start_date, offer_end_date, prospective_tenant_ids });
+			Self::do_submit_offer(applicant_id, offer_listing, offer_price, offer_start_date, offer_end_date, prospective_tenant_ids, valid_until, custom_shares)?;
 			Ok(())
 		}
 
 		#[pallet::call_index(5)]
-		#[pallet::weight(10_000 + T::DbWeight::get().writes(1).ref_time())]
+		#[pallet::weight(10_000 + Pallet::<T>::settle_offer_weight().ref_time())]
 		pub fn accept_offer(origin: OriginFor<T>, offer_id: OfferId) -> DispatchResult {
 			let landlord_id = ensure_signed(origin)?;
 			ensure!(Offers::<T>::contains_key(&offer_id), Error::<T>::OfferDoesNotExist);
-			let mut offer = Offers::<T>::get(&offer_id).unwrap();
-			let current_block_number =  frame_system::Pallet::<T>::block_number();
-			ensure!(current_block_number <= offer.valid_until, Error::<T>::OfferExpired);
-			ensure!(offer.offer_status == OfferStatus::Pending, Error::<T>::OfferCannotBeAccepted);
-			ensure!(offer.all_signed, Error::<T>::OfferNotFullySigned);
-			ensure!(offer.offer_start_date > current_block_number, Error::<T>::InvalidOfferStartDate); // add a buffer time maybe? start date must be at least curr + 100 blocks?
-			let property_id = offer.property_id;
-			ensure!(Properties::<T>::contains_key(&property_id), Error::<T>::PropertyDoesNotExist);
-			let property = Properties::<T>::get(property_id).unwrap();
-			ensure!(property.landlord_id == landlord_id, Error::<T>::Unauthorized);
-			ensure!(!Tenancies::<T>::contains_key(&property_id), Error::<T>::TenancyAlreadyExists);
-			offer.offer_status = OfferStatus::Accepted;
-			T::NativeBalance::thaw(&FreezeReason::Offer(offer_id).into(), &offer.lead_tenant);
-			T::NativeBalance::transfer(&offer.lead_tenant, &landlord_id, offer.offer_price.into(), Preserve);
-			Offers::<T>::insert(&offer_id, &offer);
-			let new_tenancy = Tenancy::new(offer);
-			Tenancies::<T>::insert(&property_id, new_tenancy);
+			let offer = Offers::<T>::get(&offer_id).unwrap();
+			ensure!(Properties::<T>::contains_key(&offer.property_id), Error::<T>::PropertyDoesNotExist);
+			let property = Properties::<T>::get(offer.property_id).unwrap();
+			ensure!(property.is_authorised(&landlord_id, AgentPermission::ManageOffers), Error::<T>::Unauthorized);
 
-			// Locked funds will be transferred to the landlord
-			// need to start thinking about multiple tenants
-			// e.g offer of 1000 accepted from 4 tenants
-			// 250 locked for each, 250 from each transferred to landlord.
-			// Also need a way to override this so that custom amounts can be locked/sent.
-			// Two flat mates might share but one has ensuite and pay 100 more than the other.
-
-			/*
-			3 tenants 1000 rent
-			333 = 1000.checked_div(3)
-			1 = 1000 - (333 * 3)
-			The lead tenant pays the extra 1.
-			*/
-			
-			Self::deposit_event(Event::OfferAccepted {offer_id});
-			// Self::deposit_event(Event::TenancyCreated {});
-			Ok(())
+			Self::do_accept_offer(offer_id)
 		}
 		
 
 		#[pallet::call_index(6)]
-		#[pallet::weight(10_000 + T::DbWeight::get().writes(1).ref_time())]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(6, 4).ref_time())]
 		pub fn sign_offer(origin: OriginFor<T>, offer_id: OfferId) -> DispatchResult {
 			let applicant_id = ensure_signed(origin)?;
 			ensure!(VerifiedApplicants::<T>::contains_key(&applicant_id), Error::<T>::Unauthorized);
@@ -313,11 +498,15 @@ pub mod pallet {
 			let current_block_number =  frame_system::Pallet::<T>::block_number();
 			ensure!(current_block_number <= offer.valid_until, Error::<T>::OfferExpired);
 			ensure!(offer.offer_status == OfferStatus::Pending, Error::<T>::OfferCannotBeAccepted);
+			let share = offer.rent_share_of(&applicant_id).ok_or(Error::<T>::Unauthorized)?;
+			ensure!(!offer.prospective_tenant_signatures.iter().any(|(app_id, signed)| *app_id == applicant_id && *signed), Error::<T>::OfferAlreadySigned);
+			Self::hold_funds(&HoldReason::Offer.into(), &applicant_id, share, Error::<T>::InsufficientFundsForRentShare)?;
 			let new_tenants = offer.prospective_tenant_signatures.into_iter().map(|(app_id, signed)| if app_id == applicant_id {(app_id, true)} else {(app_id, signed)}).collect::<Vec<(T::AccountId, bool)>>();
 			let all_signed = new_tenants.iter().all(|(applicant_id, signed)| *signed == true);
 			let updated_prospective_tenants = BoundedVec::try_from(new_tenants).map_err(|_| Error::<T>::TooManyTenants)?; // should never happen since we don't ever append 
 			offer.prospective_tenant_signatures = updated_prospective_tenants;
 			offer.all_signed = all_signed;
+			Self::schedule_auto_acceptance(&offer)?;
 			
 			Offers::<T>::insert(&offer_id, offer);
 
@@ -332,6 +521,624 @@ pub mod pallet {
 			Self::deposit_event(Event::ApplicantSignedOffer {applicant_id});
 			Ok(())
 		}
+
+		#[pallet::call_index(7)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads(2).saturating_add(Pallet::<T>::close_offer_weight()).ref_time())]
+		pub fn withdraw_offer(origin: OriginFor<T>, offer_id: OfferId) -> DispatchResult {
+			// The lead tenant or any of the co-tenants can pull the offer
+			let applicant_id = ensure_signed(origin)?;
+			ensure!(Offers::<T>::contains_key(&offer_id), Error::<T>::OfferDoesNotExist);
+			let mut offer = Offers::<T>::get(&offer_id).unwrap();
+			ensure!(offer.lead_tenant == applicant_id || offer.prospective_tenant_ids.contains(&applicant_id), Error::<T>::Unauthorized);
+			ensure!(offer.offer_status == OfferStatus::Pending, Error::<T>::OfferNotPending);
+			Self::ensure_not_leading_bid(&offer)?;
+
+			Self::close_offer(&mut offer, OfferStatus::Cancelled)?;
+
+			Self::deposit_event(Event::OfferWithdrawn {offer_id, withdrawn_by: applicant_id});
+			Ok(())
+		}
+
+		#[pallet::call_index(8)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads(2).saturating_add(Pallet::<T>::close_offer_weight()).ref_time())]
+		pub fn reject_offer(origin: OriginFor<T>, offer_id: OfferId, reason_code: Option<u8>) -> DispatchResult {
+			let landlord_id = ensure_signed(origin)?;
+			ensure!(Offers::<T>::contains_key(&offer_id), Error::<T>::OfferDoesNotExist);
+			let mut offer = Offers::<T>::get(&offer_id).unwrap();
+			ensure!(offer.offer_status == OfferStatus::Pending, Error::<T>::OfferNotPending);
+			let property_id = offer.property_id;
+			ensure!(Properties::<T>::contains_key(&property_id), Error::<T>::PropertyDoesNotExist);
+			let property = Properties::<T>::get(property_id).unwrap();
+			ensure!(property.is_authorised(&landlord_id, AgentPermission::ManageOffers), Error::<T>::Unauthorized);
+
+			Self::close_offer(&mut offer, OfferStatus::Rejected)?;
+
+			Self::deposit_event(Event::OfferRejected {offer_id, reason_code});
+			Ok(())
+		}
+
+		#[pallet::call_index(9)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 1).ref_time())]
+		pub fn propose_counter_offer(origin: OriginFor<T>, offer_id: OfferId, offer_price: u32, offer_start_date: BlockNumberFor<T>, offer_end_date: BlockNumberFor<T>) -> DispatchResult {
+			let landlord_id = ensure_signed(origin)?;
+			ensure!(Offers::<T>::contains_key(&offer_id), Error::<T>::OfferDoesNotExist);
+			let offer = Offers::<T>::get(&offer_id).unwrap();
+			let current_block_number =  frame_system::Pallet::<T>::block_number();
+			ensure!(current_block_number <= offer.valid_until, Error::<T>::OfferExpired);
+			ensure!(offer.offer_status == OfferStatus::Pending, Error::<T>::OfferNotPending);
+			ensure!(Properties::<T>::contains_key(&offer.property_id), Error::<T>::PropertyDoesNotExist);
+			let property = Properties::<T>::get(offer.property_id).unwrap();
+			ensure!(property.is_authorised(&landlord_id, AgentPermission::ManageOffers), Error::<T>::Unauthorized);
+			ensure!(Listings::<T>::contains_key(&offer.listing_id), Error::<T>::ListingDoesNotExist);
+			let offer_listing = Listings::<T>::get(&offer.listing_id).unwrap();
+			// Bids can only go up through place_bid, and sealed offers are only negotiated once every offer is revealed
+			match offer_listing.offer_mode {
+				OfferMode::Auction { .. } => return Err(Error::<T>::ListingIsAuction.into()),
+				OfferMode::Sealed { reveal_end, .. } => ensure!(current_block_number > reveal_end, Error::<T>::RevealWindowOpen),
+				OfferMode::Open => {},
+			}
+			ensure!(offer_start_date >= current_block_number
+					&& offer_start_date < offer_end_date
+					&& offer_start_date >= offer_listing.availability_date, Error::<T>::InvalidOfferStartDate);
+
+			let mut rounds = OfferNegotiations::<T>::get(&offer_id).unwrap_or(BoundedVec::new());
+			ensure!(!rounds.iter().any(|round| round.status == CounterOfferStatus::Open), Error::<T>::CounterOfferAlreadyOpen);
+			let round = rounds.len() as u32;
+			rounds.try_push(CounterOffer::new(offer_price, offer_start_date, offer_end_date, current_block_number)).map_err(|_| Error::<T>::TooManyCounterOffers)?;
+			OfferNegotiations::<T>::insert(&offer_id, rounds);
+
+			Self::deposit_event(Event::CounterOfferProposed {offer_id, round, offer_price, offer_start_date, offer_end_date});
+			Ok(())
+		}
+
+		#[pallet::call_index(10)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 3).ref_time())]
+		pub fn accept_counter_offer(origin: OriginFor<T>, offer_id: OfferId, custom_shares: Option<BoundedVec<u32, T::MaxNumberOfTenants>>) -> DispatchResult {
+			// Only the lead tenant can take the new terms since their funds back the offer.
+			// Co-tenants agree to the new terms by signing the offer again.
+			let applicant_id = ensure_signed(origin)?;
+			ensure!(Offers::<T>::contains_key(&offer_id), Error::<T>::OfferDoesNotExist);
+			let mut offer = Offers::<T>::get(&offer_id).unwrap();
+			ensure!(offer.lead_tenant == applicant_id, Error::<T>::Unauthorized);
+			let current_block_number =  frame_system::Pallet::<T>::block_number();
+			ensure!(current_block_number <= offer.valid_until, Error::<T>::OfferExpired);
+			ensure!(offer.offer_status == OfferStatus::Pending, Error::<T>::OfferNotPending);
+
+			let mut rounds = OfferNegotiations::<T>::get(&offer_id).unwrap_or(BoundedVec::new());
+			let round = rounds.iter().position(|round| round.status == CounterOfferStatus::Open).ok_or(Error::<T>::NoOpenCounterOffer)?;
+			let counter_offer = &mut rounds[round];
+			ensure!(counter_offer.offer_start_date >= current_block_number, Error::<T>::InvalidOfferStartDate);
+			let rent_shares = Self::split_rent(&applicant_id, &offer.prospective_tenant_ids, counter_offer.offer_price, custom_shares)?;
+			let lead_share = rent_shares.iter().find(|(id, _)| *id == applicant_id).map(|(_, share)| *share).unwrap_or_default();
+			counter_offer.status = CounterOfferStatus::Accepted;
+
+			// The co-tenants signed the old terms, so they have to sign again
+			Self::release_offer_funds(&offer)?;
+			offer.offer_price = counter_offer.offer_price;
+			offer.offer_start_date = counter_offer.offer_start_date;
+			offer.offer_end_date = counter_offer.offer_end_date;
+			offer.rent_shares = rent_shares;
+			offer.prospective_tenant_signatures = Self::initial_signatures(&offer.lead_tenant, &offer.prospective_tenant_ids)?;
+			offer.all_signed = offer.prospective_tenant_signatures.iter().all(|(_, signed)| *signed);
+
+			Self::hold_funds(&HoldReason::Offer.into(), &applicant_id, lead_share, Error::<T>::InsufficientFundsForOffer)?;
+			Self::schedule_auto_acceptance(&offer)?;
+			Offers::<T>::insert(&offer_id, &offer);
+			OfferNegotiations::<T>::insert(&offer_id, rounds);
+
+			Self::deposit_event(Event::CounterOfferAccepted {offer_id, round: round as u32});
+			Ok(())
+		}
+
+		#[pallet::call_index(11)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 1).ref_time())]
+		pub fn decline_counter_offer(origin: OriginFor<T>, offer_id: OfferId) -> DispatchResult {
+			// Any of the prospective tenants can turn the new terms down, the original offer stands
+			let applicant_id = ensure_signed(origin)?;
+			ensure!(Offers::<T>::contains_key(&offer_id), Error::<T>::OfferDoesNotExist);
+			let offer = Offers::<T>::get(&offer_id).unwrap();
+			ensure!(offer.lead_tenant == applicant_id || offer.prospective_tenant_ids.contains(&applicant_id), Error::<T>::Unauthorized);
+			ensure!(offer.offer_status == OfferStatus::Pending, Error::<T>::OfferNotPending);
+
+			let mut rounds = OfferNegotiations::<T>::get(&offer_id).unwrap_or(BoundedVec::new());
+			let round = rounds.iter().position(|round| round.status == CounterOfferStatus::Open).ok_or(Error::<T>::NoOpenCounterOffer)?;
+			rounds[round].status = CounterOfferStatus::Declined;
+			OfferNegotiations::<T>::insert(&offer_id, rounds);
+
+			Self::deposit_event(Event::CounterOfferDeclined {offer_id, round: round as u32, declined_by: applicant_id});
+			Ok(())
+		}
+
+		#[pallet::call_index(12)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(6, 4).ref_time())]
+		pub fn amend_offer(origin: OriginFor<T>, offer_id: OfferId, offer_price: u32, offer_start_date: BlockNumberFor<T>, offer_end_date: BlockNumberFor<T>, prospective_tenant_ids: BoundedVec<T::AccountId, T::MaxNumberOfTenants>, valid_until: BlockNumberFor<T>, custom_shares: Option<BoundedVec<u32, T::MaxNumberOfTenants>>) -> DispatchResult {
+			let applicant_id = ensure_signed(origin)?;
+			ensure!(Offers::<T>::contains_key(&offer_id), Error::<T>::OfferDoesNotExist);
+			let mut offer = Offers::<T>::get(&offer_id).unwrap();
+			ensure!(offer.lead_tenant == applicant_id, Error::<T>::Unauthorized);
+			ensure!(offer.offer_status == OfferStatus::Pending, Error::<T>::OfferNotPending);
+			// The landlord's counter offer has to be answered first
+			let rounds = OfferNegotiations::<T>::get(&offer_id).unwrap_or(BoundedVec::new());
+			ensure!(!rounds.iter().any(|round| round.status == CounterOfferStatus::Open), Error::<T>::CounterOfferAlreadyOpen);
+			ensure!(Listings::<T>::contains_key(&offer.listing_id), Error::<T>::ListingDoesNotExist);
+			let offer_listing = Listings::<T>::get(&offer.listing_id).unwrap();
+			// A revealed sealed offer is final, otherwise it could be changed after seeing the other reveals.
+			// Bids can only go up through place_bid.
+			match offer_listing.offer_mode {
+				OfferMode::Open => {},
+				OfferMode::Sealed { .. } => return Err(Error::<T>::ListingIsSealed.into()),
+				OfferMode::Auction { .. } => return Err(Error::<T>::ListingIsAuction.into()),
+			}
+			Self::ensure_valid_offer_terms(&applicant_id, &offer_listing, offer_start_date, offer_end_date, &prospective_tenant_ids, valid_until)?;
+			let rent_shares = Self::split_rent(&applicant_id, &prospective_tenant_ids, offer_price, custom_shares)?;
+
+			if valid_until != offer.valid_until {
+				Self::reschedule_expiry(offer_id, offer.valid_until, valid_until)?;
+			}
+			// Co-tenants agreed to the old terms, so everyone but the lead has to sign again
+			Self::release_offer_funds(&offer)?;
+			offer.offer_price = offer_price;
+			offer.offer_start_date = offer_start_date;
+			offer.offer_end_date = offer_end_date;
+			offer.valid_until = valid_until;
+			offer.prospective_tenant_signatures = Self::initial_signatures(&applicant_id, &prospective_tenant_ids)?;
+			offer.all_signed = offer.prospective_tenant_signatures.iter().all(|(_, signed)| *signed);
+			offer.prospective_tenant_ids = prospective_tenant_ids.clone();
+			offer.rent_shares = rent_shares;
+
+			let lead_share = offer.rent_share_of(&applicant_id).unwrap_or_default();
+			Self::hold_funds(&HoldReason::Offer.into(), &applicant_id, lead_share, Error::<T>::InsufficientFundsForOffer)?;
+			Self::schedule_auto_acceptance(&offer)?;
+			Offers::<T>::insert(&offer_id, &offer);
+
+			Self::deposit_event(Event::OfferAmended {offer_id, offer_price, offer_start_date, offer_end_date, prospective_tenant_ids});
+			Ok(())
+		}
+
+		#[pallet::call_index(13)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads(2).saturating_add(Pallet::<T>::close_offer_weight()).ref_time())]
+		pub fn decline_offer(origin: OriginFor<T>, offer_id: OfferId) -> DispatchResult {
+			// A co-tenant who does not want to join cancels the offer for everyone.
+			// If the lead wants to keep the offer with someone else they can swap the tenant with amend_offer first.
+			let applicant_id = ensure_signed(origin)?;
+			ensure!(Offers::<T>::contains_key(&offer_id), Error::<T>::OfferDoesNotExist);
+			let mut offer = Offers::<T>::get(&offer_id).unwrap();
+			ensure!(offer.prospective_tenant_ids.contains(&applicant_id), Error::<T>::Unauthorized);
+			ensure!(offer.lead_tenant != applicant_id, Error::<T>::LeadTenantCannotDecline);
+			ensure!(offer.offer_status == OfferStatus::Pending, Error::<T>::OfferNotPending);
+			Self::ensure_not_leading_bid(&offer)?;
+
+			Self::close_offer(&mut offer, OfferStatus::Cancelled)?;
+
+			Self::deposit_event(Event::OfferDeclined {offer_id, applicant_id});
+			Ok(())
+		}
+
+		#[pallet::call_index(14)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 3).ref_time())]
+		pub fn revoke_signature(origin: OriginFor<T>, offer_id: OfferId) -> DispatchResult {
+			let applicant_id = ensure_signed(origin)?;
+			ensure!(Offers::<T>::contains_key(&offer_id), Error::<T>::OfferDoesNotExist);
+			let mut offer = Offers::<T>::get(&offer_id).unwrap();
+			ensure!(offer.prospective_tenant_ids.contains(&applicant_id), Error::<T>::Unauthorized);
+			ensure!(offer.lead_tenant != applicant_id, Error::<T>::LeadTenantCannotDecline);
+			ensure!(offer.offer_status == OfferStatus::Pending, Error::<T>::OfferNotPending);
+			ensure!(offer.prospective_tenant_signatures.iter().any(|(app_id, signed)| *app_id == applicant_id && *signed), Error::<T>::OfferNotSigned);
+			Self::ensure_not_leading_bid(&offer)?;
+
+			offer.prospective_tenant_signatures.iter_mut()
+				.filter(|(app_id, _)| *app_id == applicant_id)
+				.for_each(|(_, signed)| *signed = false);
+			offer.all_signed = false;
+			let share = offer.rent_share_of(&applicant_id).unwrap_or_default();
+			Self::release_held(&HoldReason::Offer.into(), &applicant_id, share.into())?;
+			Offers::<T>::insert(&offer_id, &offer);
+
+			Self::deposit_event(Event::ApplicantRevokedSignature {offer_id, applicant_id});
+			Ok(())
+		}
+
+		#[pallet::call_index(15)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 3).ref_time())]
+		pub fn commit_sealed_offer(origin: OriginFor<T>, listing_id: ListingId, commitment: T::Hash, max_price: u32) -> DispatchResult {
+			let applicant_id = ensure_signed(origin)?;
+			ensure!(VerifiedApplicants::<T>::contains_key(&applicant_id), Error::<T>::Unauthorized);
+			ensure!(Listings::<T>::contains_key(&listing_id), Error::<T>::ListingDoesNotExist);
+			let offer_listing = Listings::<T>::get(&listing_id).unwrap();
+			ensure!(offer_listing.is_open(), Error::<T>::ListingNotOpen);
+			let OfferMode::Sealed { commit_end, .. } = offer_listing.offer_mode else {
+				return Err(Error::<T>::ListingIsNotSealed.into())
+			};
+			let current_block_number =  frame_system::Pallet::<T>::block_number();
+			ensure!(current_block_number <= commit_end, Error::<T>::CommitWindowClosed);
+			// Checked again on reveal, but without it the commitment would hold funds for an offer that can't be made
+			if offer_listing.attributes.viewing_required {
+				ensure!(ViewingAttendance::<T>::contains_key(&listing_id, &applicant_id), Error::<T>::ViewingNotAttended);
+			}
+			ensure!(!SealedBids::<T>::contains_key(&listing_id, &applicant_id), Error::<T>::AlreadyOfferedOnListing);
+			ensure!(Properties::<T>::contains_key(&offer_listing.property_id), Error::<T>::PropertyDoesNotExist);
+			let property = Properties::<T>::get(offer_listing.property_id).unwrap();
+			ensure!(!property.is_managed_by(&applicant_id) && offer_listing.lister != applicant_id, Error::<T>::LandlordCannotOfferOnOwnProperty);
+
+			let mut sealed_bidders = ListingSealedBidders::<T>::get(&listing_id).unwrap_or(BoundedVec::new());
+			sealed_bidders.try_push(applicant_id.clone()).map_err(|_| Error::<T>::TooManyOffersOnListing)?;
+			ListingSealedBidders::<T>::insert(&listing_id, sealed_bidders);
+			SealedBids::<T>::insert(&listing_id, &applicant_id, SealedBid { commitment, max_price, committed_at: current_block_number });
+
+			Self::hold_funds(&HoldReason::SealedBid.into(), &applicant_id, max_price, Error::<T>::InsufficientFundsForOffer)?;
+
+			Self::deposit_event(Event::SealedOfferCommitted {listing_id, applicant_id});
+			Ok(())
+		}
+
+		#[pallet::call_index(16)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(
+			24 + T::MaxNumberOfTenants::get() as u64,
+			20,
+		).ref_time())]
+		pub fn reveal_sealed_offer(origin: OriginFor<T>, listing_id: ListingId, offer_price: u32, offer_start_date: BlockNumberFor<T>, offer_end_date: BlockNumberFor<T>, prospective_tenant_ids: BoundedVec<T::AccountId, T::MaxNumberOfTenants>, valid_until: BlockNumberFor<T>, custom_shares: Option<BoundedVec<u32, T::MaxNumberOfTenants>>, salt: T::Hash) -> DispatchResult {
+			let applicant_id = ensure_signed(origin)?;
+			ensure!(Listings::<T>::contains_key(&listing_id), Error::<T>::ListingDoesNotExist);
+			let offer_listing = Listings::<T>::get(&listing_id).unwrap();
+			let OfferMode::Sealed { commit_end, reveal_end } = offer_listing.offer_mode else {
+				return Err(Error::<T>::ListingIsNotSealed.into())
+			};
+			let current_block_number =  frame_system::Pallet::<T>::block_number();
+			ensure!(current_block_number > commit_end && current_block_number <= reveal_end, Error::<T>::NotInRevealWindow);
+			let sealed_bid = SealedBids::<T>::get(&listing_id, &applicant_id).ok_or(Error::<T>::SealedBidDoesNotExist)?;
+			let revealed = T::Hashing::hash_of(&(listing_id, &applicant_id, offer_price, offer_start_date, offer_end_date, salt));
+			ensure!(revealed == sealed_bid.commitment, Error::<T>::CommitmentMismatch);
+			ensure!(offer_price <= sealed_bid.max_price, Error::<T>::OfferAboveSealedMaximum);
+
+			// The commitment's hold makes way for the regular offer hold on the lead tenant's share
+			Self::remove_sealed_bid(listing_id, &applicant_id)?;
+			let offer_id = Self::do_submit_offer(applicant_id, offer_listing, offer_price, offer_start_date, offer_end_date, prospective_tenant_ids, valid_until, custom_shares)?;
+
+			Self::deposit_event(Event::SealedOfferRevealed {listing_id, offer_id});
+			Ok(())
+		}
+
+		#[pallet::call_index(17)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 2).ref_time())]
+		pub fn update_listing(origin: OriginFor<T>, listing_id: ListingId, rental_price: Option<u32>, availability_date: Option<BlockNumberFor<T>>) -> DispatchResult {
+			let landlord_id = ensure_signed(origin)?;
+			ensure!(Listings::<T>::contains_key(&listing_id), Error::<T>::ListingDoesNotExist);
+			let mut listing = Listings::<T>::get(&listing_id).unwrap();
+			ensure!(Properties::<T>::contains_key(&listing.property_id), Error::<T>::PropertyDoesNotExist);
+			let property = Properties::<T>::get(listing.property_id).unwrap();
+			ensure!(property.is_authorised(&landlord_id, AgentPermission::ManageListings), Error::<T>::Unauthorized);
+			ensure!(listing.is_open(), Error::<T>::ListingNotOpen);
+
+			if let Some(rental_price) = rental_price {
+				listing.rental_price = rental_price;
+			}
+			if let Some(availability_date) = availability_date {
+				listing.availability_date = availability_date;
+			}
+			// A lower asking price can bring signed offers up to it, so check again next block
+			if listing.acceptance_policy == AcceptancePolicy::FirstAtAsking {
+				let current_block_number =  frame_system::Pallet::<T>::block_number();
+				Self::schedule_listing_deadline(listing_id, current_block_number.saturating_add(One::one()))?;
+			}
+			Listings::<T>::insert(&listing_id, &listing);
+
+			Self::deposit_event(Event::ListingUpdated {listing_id, rental_price: listing.rental_price, availability_date: listing.availability_date});
+			Ok(())
+		}
+
+		// Every pending offer is closed, and every sealed bidder's commitment is taken and its hold released
+		#[pallet::call_index(18)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(
+			6 + 5 * T::MaxOffersPerListing::get() as u64,
+			6 + 4 * T::MaxOffersPerListing::get() as u64,
+		).saturating_add(Pallet::<T>::close_offer_weight().saturating_mul(T::MaxOffersPerListing::get() as u64)).ref_time())]
+		pub fn close_listing(origin: OriginFor<T>, listing_id: ListingId) -> DispatchResult {
+			let landlord_id = ensure_signed(origin)?;
+			ensure!(Listings::<T>::contains_key(&listing_id), Error::<T>::ListingDoesNotExist);
+			let mut listing = Listings::<T>::get(&listing_id).unwrap();
+			ensure!(Properties::<T>::contains_key(&listing.property_id), Error::<T>::PropertyDoesNotExist);
+			let property = Properties::<T>::get(listing.property_id).unwrap();
+			ensure!(property.is_authorised(&landlord_id, AgentPermission::ManageListings), Error::<T>::Unauthorized);
+			ensure!(listing.is_open(), Error::<T>::ListingNotOpen);
+
+			// Every pending offer is rejected and its funds released
+			let offers_on_listing = ListingOffers::<T>::take(&listing_id).unwrap_or_default();
+			for offer_id in offers_on_listing {
+				let Some(mut offer) = Offers::<T>::get(&offer_id) else { continue };
+				if offer.offer_status != OfferStatus::Pending {
+					continue;
+				}
+				Self::close_offer(&mut offer, OfferStatus::Rejected)?;
+				Self::deposit_event(Event::OfferRejected {offer_id, reason_code: None});
+			}
+			// Unrevealed commitments on a sealed listing are handed back as well
+			for applicant_id in ListingSealedBidders::<T>::get(&listing_id).unwrap_or_default() {
+				Self::remove_sealed_bid(listing_id, &applicant_id)?;
+			}
+			listing.status = ListingStatus::Closed;
+			Listings::<T>::insert(&listing_id, &listing);
+			PropertyOpenListing::<T>::remove(&listing.property_id);
+			AuctionLeaders::<T>::remove(&listing_id);
+			Self::release_held(&HoldReason::ListingDeposit.into(), &listing.lister, listing.deposit)?;
+
+			Self::deposit_event(Event::ListingClosed {listing_id});
+			Ok(())
+		}
+
+		#[pallet::call_index(19)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 1).ref_time())]
+		pub fn publish_viewing_slots(origin: OriginFor<T>, listing_id: ListingId, slots: BoundedVec<(BlockNumberFor<T>, BlockNumberFor<T>), T::MaxViewingSlotsPerListing>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Listings::<T>::contains_key(&listing_id), Error::<T>::ListingDoesNotExist);
+			let listing = Listings::<T>::get(&listing_id).unwrap();
+			Self::ensure_can_manage_listing(&who, &listing)?;
+			ensure!(listing.is_open(), Error::<T>::ListingNotOpen);
+
+			let current_block_number =  frame_system::Pallet::<T>::block_number();
+			let mut viewing_slots = ViewingSlots::<T>::get(&listing_id).unwrap_or(BoundedVec::new());
+			let number_of_slots = slots.len() as u32;
+			for (start, end) in slots {
+				ensure!(start > current_block_number && start < end, Error::<T>::InvalidViewingSlot);
+				// Slots that have ended make way for new ones, so the index of a slot still to come never changes.
+				// A booked slot is only replaced once its attendance is marked, so the booking isn't lost before then.
+				match viewing_slots.iter().position(|slot| slot.end < current_block_number && (slot.booked_by.is_none() || slot.attended)) {
+					Some(slot_index) => viewing_slots[slot_index] = ViewingSlot::new(start, end),
+					None => viewing_slots.try_push(ViewingSlot::new(start, end)).map_err(|_| Error::<T>::TooManyViewingSlots)?,
+				}
+			}
+			ViewingSlots::<T>::insert(&listing_id, viewing_slots);
+
+			Self::deposit_event(Event::ViewingSlotsPublished {listing_id, number_of_slots});
+			Ok(())
+		}
+
+		#[pallet::call_index(20)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 1).ref_time())]
+		pub fn book_viewing(origin: OriginFor<T>, listing_id: ListingId, slot_index: u32) -> DispatchResult {
+			let applicant_id = ensure_signed(origin)?;
+			ensure!(VerifiedApplicants::<T>::contains_key(&applicant_id), Error::<T>::Unauthorized);
+			ensure!(Listings::<T>::contains_key(&listing_id), Error::<T>::ListingDoesNotExist);
+			let listing = Listings::<T>::get(&listing_id).unwrap();
+			ensure!(listing.is_open(), Error::<T>::ListingNotOpen);
+
+			let mut viewing_slots = ViewingSlots::<T>::get(&listing_id).unwrap_or(BoundedVec::new());
+			let current_block_number =  frame_system::Pallet::<T>::block_number();
+			// One upcoming booking per applicant per listing, so slots aren't hoarded
+			ensure!(!viewing_slots.iter().any(|slot| slot.booked_by.as_ref() == Some(&applicant_id) && !slot.attended && slot.end >= current_block_number), Error::<T>::ViewingAlreadyBooked);
+			let slot = viewing_slots.get_mut(slot_index as usize).ok_or(Error::<T>::ViewingSlotDoesNotExist)?;
+			ensure!(slot.booked_by.is_none(), Error::<T>::ViewingSlotAlreadyBooked);
+			ensure!(current_block_number < slot.start, Error::<T>::ViewingAlreadyStarted);
+			slot.booked_by = Some(applicant_id.clone());
+			ViewingSlots::<T>::insert(&listing_id, viewing_slots);
+
+			Self::deposit_event(Event::ViewingBooked {listing_id, slot_index, applicant_id});
+			Ok(())
+		}
+
+		#[pallet::call_index(21)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1).ref_time())]
+		pub fn cancel_viewing(origin: OriginFor<T>, listing_id: ListingId, slot_index: u32) -> DispatchResult {
+			let applicant_id = ensure_signed(origin)?;
+			let mut viewing_slots = ViewingSlots::<T>::get(&listing_id).unwrap_or(BoundedVec::new());
+			let slot = viewing_slots.get_mut(slot_index as usize).ok_or(Error::<T>::ViewingSlotDoesNotExist)?;
+			ensure!(slot.booked_by.as_ref() == Some(&applicant_id), Error::<T>::Unauthorized);
+			let current_block_number =  frame_system::Pallet::<T>::block_number();
+			ensure!(current_block_number < slot.start, Error::<T>::ViewingAlreadyStarted);
+			slot.booked_by = None;
+			ViewingSlots::<T>::insert(&listing_id, viewing_slots);
+
+			Self::deposit_event(Event::ViewingCancelled {listing_id, slot_index, applicant_id});
+			Ok(())
+		}
+
+		#[pallet::call_index(22)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 2).ref_time())]
+		pub fn mark_viewing_attended(origin: OriginFor<T>, listing_id: ListingId, slot_index: u32) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Listings::<T>::contains_key(&listing_id), Error::<T>::ListingDoesNotExist);
+			let listing = Listings::<T>::get(&listing_id).unwrap();
+			Self::ensure_can_manage_listing(&who, &listing)?;
+
+			let mut viewing_slots = ViewingSlots::<T>::get(&listing_id).unwrap_or(BoundedVec::new());
+			let slot = viewing_slots.get_mut(slot_index as usize).ok_or(Error::<T>::ViewingSlotDoesNotExist)?;
+			let applicant_id = slot.booked_by.clone().ok_or(Error::<T>::ViewingSlotNotBooked)?;
+			let current_block_number =  frame_system::Pallet::<T>::block_number();
+			ensure!(current_block_number >= slot.start, Error::<T>::ViewingNotStarted);
+			slot.attended = true;
+			ViewingSlots::<T>::insert(&listing_id, viewing_slots);
+			ViewingAttendance::<T>::insert(&listing_id, &applicant_id, ());
+
+			Self::deposit_event(Event::ViewingAttended {listing_id, slot_index, applicant_id});
+			Ok(())
+		}
+
+		#[pallet::call_index(23)]
+		// Closing the outbid offer on top of submitting the new one
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(
+			22 + T::MaxNumberOfTenants::get() as u64,
+			17,
+		).saturating_add(Pallet::<T>::close_offer_weight()).ref_time())]
+		pub fn place_bid(origin: OriginFor<T>, listing_id: ListingId, bid_price: u32, offer_start_date: BlockNumberFor<T>, offer_end_date: BlockNumberFor<T>, prospective_tenant_ids: BoundedVec<T::AccountId, T::MaxNumberOfTenants>, custom_shares: Option<BoundedVec<u32, T::MaxNumberOfTenants>>) -> DispatchResult {
+			let applicant_id = ensure_signed(origin)?;
+			ensure!(VerifiedApplicants::<T>::contains_key(&applicant_id), Error::<T>::Unauthorized);
+			ensure!(Listings::<T>::contains_key(&listing_id), Error::<T>::ListingDoesNotExist);
+			let offer_listing = Listings::<T>::get(&listing_id).unwrap();
+			let OfferMode::Auction { reserve_price, end } = offer_listing.offer_mode else {
+				return Err(Error::<T>::ListingIsNotAuction.into())
+			};
+			let current_block_number =  frame_system::Pallet::<T>::block_number();
+			ensure!(current_block_number <= end, Error::<T>::AuctionEnded);
+			ensure!(bid_price >= reserve_price, Error::<T>::BidBelowReserve);
+			// A bid knocks out the one before it, so it has to be able to win as it stands.
+			// Co-tenants can only sign an offer once it exists, which leaves bids to a single tenant.
+			ensure!(prospective_tenant_ids.len() == 1, Error::<T>::BidNotFullySigned);
+
+			// The outbid offer is closed straight away so its funds are released,
+			// bidders raising their own bid replace their previous one
+			let leading_offer = AuctionLeaders::<T>::get(&listing_id)
+				.and_then(|offer_id| Offers::<T>::get(&offer_id))
+				.filter(|offer| offer.offer_status == OfferStatus::Pending);
+			if let Some(mut leading_offer) = leading_offer {
+				ensure!(bid_price > leading_offer.offer_price, Error::<T>::BidTooLow);
+				Self::close_offer(&mut leading_offer, OfferStatus::Rejected)?;
+				Self::deposit_event(Event::Outbid {listing_id, offer_id: leading_offer.offer_id});
+			}
+
+			// Bids have to stay valid until the auction is settled
+			let valid_until = end.saturating_add(One::one());
+			let offer_id = Self::do_submit_offer(applicant_id.clone(), offer_listing, bid_price, offer_start_date, offer_end_date, prospective_tenant_ids, valid_until, custom_shares)?;
+			AuctionLeaders::<T>::insert(&listing_id, offer_id);
+
+			Self::deposit_event(Event::BidPlaced {listing_id, offer_id, bidder: applicant_id, bid_price});
+			Ok(())
+		}
+
+		#[pallet::call_index(24)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1).ref_time())]
+		pub fn assign_agent(origin: OriginFor<T>, property_id: PropertyId, agent_id: T::AccountId, permissions: AgentPermissions, commission: Permill) -> DispatchResult {
+			let landlord_id = ensure_signed(origin)?;
+			ensure!(Properties::<T>::contains_key(&property_id), Error::<T>::PropertyDoesNotExist);
+			let mut property = Properties::<T>::get(property_id).unwrap();
+			ensure!(property.landlord_id == landlord_id, Error::<T>::Unauthorized);
+			ensure!(agent_id != landlord_id, Error::<T>::LandlordCannotBeAgent);
+
+			// Assigning an agent who is already on the property changes their terms
+			match property.assigned_agents.iter_mut().find(|assignment| assignment.agent_id == agent_id) {
+				Some(assignment) => {
+					assignment.permissions = permissions;
+					assignment.commission = commission;
+				},
+				None => property.assigned_agents.try_push(AgentAssignment { agent_id: agent_id.clone(), permissions, commission }).map_err(|_| Error::<T>::TooManyAgents)?,
+			}
+			Properties::<T>::insert(&property_id, property);
+
+			Self::deposit_event(Event::AgentAssigned {property_id, agent_id, permissions, commission});
+			Ok(())
+		}
+
+		#[pallet::call_index(25)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1).ref_time())]
+		pub fn remove_agent(origin: OriginFor<T>, property_id: PropertyId, agent_id: T::AccountId) -> DispatchResult {
+			let landlord_id = ensure_signed(origin)?;
+			ensure!(Properties::<T>::contains_key(&property_id), Error::<T>::PropertyDoesNotExist);
+			let mut property = Properties::<T>::get(property_id).unwrap();
+			ensure!(property.landlord_id == landlord_id, Error::<T>::Unauthorized);
+			ensure!(property.assigned_agents.iter().any(|assignment| assignment.agent_id == agent_id), Error::<T>::AgentNotAssigned);
+
+			property.assigned_agents.retain(|assignment| assignment.agent_id != agent_id);
+			Properties::<T>::insert(&property_id, property);
+
+			Self::deposit_event(Event::AgentRemoved {property_id, agent_id});
+			Ok(())
+		}
+
+		#[pallet::call_index(26)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(
+			4 + T::MaxPendingRegistrations::get() as u64,
+			4,
+		).ref_time())]
+		pub fn request_property_registration(origin: OriginFor<T>, address: T::Hash, postal_code: T::Hash, evidence: T::Hash) -> DispatchResult {
+			let landlord_id = ensure_signed(origin)?;
+			ensure!(VerifiedLandlords::<T>::contains_key(&landlord_id), Error::<T>::Unauthorized);
+			ensure!(!PropertyKeys::<T>::contains_key(Self::property_key(&address, &postal_code)), Error::<T>::PropertyAlreadyRegistered);
+			let mut pending = PendingRegistrations::<T>::get();
+			let already_requested = pending.iter()
+				.filter_map(|request_id| RegistrationRequests::<T>::get(request_id))
+				.any(|request| request.address == address && request.postal_code == postal_code);
+			ensure!(!already_requested, Error::<T>::RegistrationAlreadyRequested);
+
+			let request_count = RegistrationRequestCounter::<T>::get().unwrap_or_default();
+			ensure!(request_count.checked_add(1).is_some(), Error::<T>::TooManyRegistrationRequests);
+			let request_id = request_count + 1;
+			pending.try_push(request_id).map_err(|_| Error::<T>::TooManyPendingRegistrations)?;
+
+			let deposit = T::RegistrationDeposit::get();
+			if !deposit.is_zero() {
+				T::NativeBalance::hold(&HoldReason::RegistrationDeposit.into(), &landlord_id, deposit)
+					.map_err(|_| Error::<T>::InsufficientFundsForRegistrationDeposit)?;
+			}
+			let requested_at = frame_system::Pallet::<T>::block_number();
+			let request = RegistrationRequest { request_id, landlord_id: landlord_id.clone(), address, postal_code, evidence, deposit, requested_at };
+			RegistrationRequests::<T>::insert(&request_id, request);
+			PendingRegistrations::<T>::put(pending);
+			RegistrationRequestCounter::<T>::put(request_id);
+
+			Self::deposit_event(Event::PropertyRegistrationRequested {request_id, landlord_id, address, postal_code, evidence});
+			Ok(())
+		}
+
+		#[pallet::call_index(27)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(5, 6).ref_time())]
+		pub fn approve_property_registration(origin: OriginFor<T>, request_id: RegistrationRequestId) -> DispatchResult {
+			T::RegistrarOrigin::ensure_origin(origin)?;
+			let request = Self::take_registration_request(request_id)?;
+
+			Self::release_held(&HoldReason::RegistrationDeposit.into(), &request.landlord_id, request.deposit)?;
+			let property_id = Self::do_register_property(request.address, request.postal_code, request.landlord_id)?;
+
+			Self::deposit_event(Event::PropertyRegistrationApproved {request_id, property_id});
+			Ok(())
+		}
+
+		#[pallet::call_index(28)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 3).ref_time())]
+		pub fn reject_property_registration(origin: OriginFor<T>, request_id: RegistrationRequestId, slash_deposit: bool) -> DispatchResult {
+			T::RegistrarOrigin::ensure_origin(origin)?;
+			let request = Self::take_registration_request(request_id)?;
+
+			let reason = HoldReason::RegistrationDeposit.into();
+			if slash_deposit {
+				T::NativeBalance::burn_held(&reason, &request.landlord_id, request.deposit, Precision::BestEffort, Fortitude::Force)?;
+			} else {
+				Self::release_held(&reason, &request.landlord_id, request.deposit)?;
+			}
+
+			Self::deposit_event(Event::PropertyRegistrationRejected {request_id, deposit_slashed: slash_deposit});
+			Ok(())
+		}
+
+		// Ends a tenancy once its term is over and settles the security deposit, the landlord keeps
+		// retained_deposit of it to cover any damage and the rest goes back to the lead tenant.
+		// Called by the landlord or an agent managing the tenancy. If neither does within DepositReturnPeriod,
+		// the lead tenant can end it and get all of it back.
+		#[pallet::call_index(29)]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(6, 5).ref_time())]
+		pub fn end_tenancy(origin: OriginFor<T>, property_id: PropertyId, retained_deposit: u32) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Tenancies::<T>::contains_key(&property_id), Error::<T>::TenancyDoesNotExist);
+			let tenancy = Tenancies::<T>::get(&property_id).unwrap();
+			ensure!(Properties::<T>::contains_key(&property_id), Error::<T>::PropertyDoesNotExist);
+			let property = Properties::<T>::get(&property_id).unwrap();
+			let current_block_number =  frame_system::Pallet::<T>::block_number();
+			ensure!(current_block_number >= tenancy.end_date, Error::<T>::TenancyNotEnded);
+			if who == tenancy.lead_tenant {
+				ensure!(current_block_number >= tenancy.end_date.saturating_add(T::DepositReturnPeriod::get()), Error::<T>::DepositReturnPeriodNotOver);
+				ensure!(retained_deposit == 0, Error::<T>::RetainedDepositTooHigh);
+			} else {
+				ensure!(property.is_authorised(&who, AgentPermission::ManageTenancy), Error::<T>::Unauthorized);
+				ensure!(retained_deposit <= tenancy.security_deposit, Error::<T>::RetainedDepositTooHigh);
+			}
+
+			let reason = HoldReason::SecurityDeposit.into();
+			if retained_deposit > 0 {
+				T::NativeBalance::transfer_on_hold(
+					&reason,
+					&tenancy.lead_tenant,
+					&property.landlord_id,
+					retained_deposit.into(),
+					Precision::Exact,
+					Restriction::Free,
+					Fortitude::Polite,
+				)?;
+			}
+			let deposit_returned = tenancy.security_deposit - retained_deposit;
+			Self::release_held(&reason, &tenancy.lead_tenant, deposit_returned.into())?;
+			// The property can be listed again
+			Tenancies::<T>::remove(&property_id);
+
+			Self::deposit_event(Event::TenancyEnded {property_id, offer_id: tenancy.offer_id, deposit_returned, deposit_retained: retained_deposit});
+			Ok(())
+		}
 	}
 
 
@@ -343,5 +1150,596 @@ pub mod pallet {
 		pub fn get_property(property_id: PropertyId) -> Option<Property<T>> {
 			Properties::<T>::get(&property_id)
 		}
+
+		// Listings currently on the market that match the filter, for front ends to query through the runtime.
+		pub fn find_listings(filter: ListingFilter) -> Vec<Listing<T>> {
+			PropertyOpenListing::<T>::iter_values()
+				.filter_map(|listing_id| Listings::<T>::get(&listing_id))
+				.filter(|listing| listing.is_open() && filter.matches(listing))
+				.collect()
+		}
+
+		// Every listing the property has had, including closed and let ones, oldest first.
+		pub fn get_property_listings(property_id: PropertyId) -> Vec<Listing<T>> {
+			let mut listings: Vec<Listing<T>> = PropertyListings::<T>::iter_key_prefix(&property_id)
+				.filter_map(|listing_id| Listings::<T>::get(&listing_id))
+				.collect();
+			listings.sort_by_key(|listing| listing.listing_id);
+			listings
+		}
+
+		// The key a property is registered under, the same address and postal code always give the same key.
+		pub fn property_key(address: &T::Hash, postal_code: &T::Hash) -> T::Hash {
+			T::Hashing::hash_of(&(address, postal_code))
+		}
+
+		pub fn get_property_by_address(address: T::Hash, postal_code: T::Hash) -> Option<Property<T>> {
+			PropertyKeys::<T>::get(Self::property_key(&address, &postal_code)).and_then(|property_id| Properties::<T>::get(&property_id))
+		}
+
+		// Registration requests still waiting on the registrar, oldest first.
+		pub fn get_pending_registrations() -> Vec<RegistrationRequest<T>> {
+			PendingRegistrations::<T>::get().into_iter()
+				.filter_map(|request_id| RegistrationRequests::<T>::get(&request_id))
+				.collect()
+		}
+
+		// Removes a request from the queue once the registrar has decided on it.
+		fn take_registration_request(request_id: RegistrationRequestId) -> Result<RegistrationRequest<T>, DispatchError> {
+			let request = RegistrationRequests::<T>::take(&request_id).ok_or(Error::<T>::RegistrationRequestDoesNotExist)?;
+			PendingRegistrations::<T>::mutate(|pending| pending.retain(|id| *id != request_id));
+			Ok(request)
+		}
+
+		fn do_register_property(address: T::Hash, postal_code: T::Hash, landlord_id: T::AccountId) -> Result<PropertyId, DispatchError> {
+			let property_key = Self::property_key(&address, &postal_code);
+			ensure!(!PropertyKeys::<T>::contains_key(&property_key), Error::<T>::PropertyAlreadyRegistered);
+
+			let property_count = PropertyCounter::<T>::get().unwrap_or_default();
+			ensure!(property_count.checked_add(1).is_some(), Error::<T>::TooManyProperties);
+			let new_property_id = property_count + 1;
+			let new_property = Property::new(new_property_id, landlord_id, address, postal_code);
+
+			Properties::<T>::insert(&new_property_id, new_property);
+			PropertyKeys::<T>::insert(&property_key, new_property_id);
+			PropertyCounter::<T>::put(new_property_id);
+
+			Self::deposit_event(Event::NewPropertyRegistered { address, postal_code });
+			Ok(new_property_id)
+		}
+
+		pub fn get_applicant_offer(applicant_id: T::AccountId, listing_id: ListingId) -> Option<Offer<T>> {
+			ApplicantListingOffers::<T>::get(&applicant_id, &listing_id).and_then(|offer_id| Offers::<T>::get(&offer_id))
+		}
+
+		// Cost of closing a single pending offer: a release for every tenant's share, the deposit
+		// releases and the ListingOffers, ApplicantOffers, OfferExpiries and counter-offer bookkeeping.
+		fn close_offer_weight() -> Weight {
+			let tenants = T::MaxNumberOfTenants::get() as u64;
+			T::DbWeight::get().reads_writes(12 + 2 * tenants, 13 + 2 * tenants)
+		}
+
+		// Cost of settling an offer into a tenancy: the rent transfers for every tenant, the deposit
+		// moves, and closing every competing offer left on the listing.
+		fn settle_offer_weight() -> Weight {
+			let tenants = T::MaxNumberOfTenants::get() as u64;
+			let offers = T::MaxOffersPerListing::get() as u64;
+			T::DbWeight::get().reads_writes(15 + 6 * tenants, 12 + 6 * tenants)
+				.saturating_add(T::DbWeight::get().reads(offers))
+				.saturating_add(Self::close_offer_weight().saturating_mul(offers))
+		}
+
+		// Moves an offer out of the pending state, releasing the tenants' held funds
+		// and freeing its slots in ListingOffers, ApplicantOffers and OfferExpiries.
+		fn close_offer(offer: &mut Offer<T>, status: OfferStatus) -> DispatchResult {
+			let offer_id = offer.offer_id;
+			Self::unschedule_expiry(offer_id, offer.valid_until);
+			Self::release_offer_funds(offer)?;
+			Self::release_held(&HoldReason::HoldingDeposit.into(), &offer.lead_tenant, offer.holding_deposit.into())?;
+			Self::release_held(&HoldReason::SecurityDeposit.into(), &offer.lead_tenant, offer.security_deposit.into())?;
+			Self::release_held(&HoldReason::OfferDeposit.into(), &offer.lead_tenant, offer.deposit)?;
+
+			ListingOffers::<T>::mutate(&offer.listing_id, |maybe_offers| {
+				if let Some(offers) = maybe_offers {
+					offers.retain(|id| *id != offer_id);
+				}
+			});
+			ApplicantOffers::<T>::mutate(&offer.lead_tenant, |maybe_offers| {
+				if let Some(offers) = maybe_offers {
+					offers.retain(|id| *id != offer_id);
+				}
+			});
+			ApplicantListingOffers::<T>::remove(&offer.lead_tenant, &offer.listing_id);
+			Self::refresh_listing_status(offer.listing_id);
+
+			Self::lapse_counter_offer(offer_id);
+			offer.offer_status = status;
+			Offers::<T>::insert(&offer_id, &*offer);
+			Ok(())
+		}
+
+		// Settles an offer into a tenancy. Used by accept_offer and by listings' acceptance policies,
+		// callers are responsible for checking who is allowed to accept.
+		// Settlement moves money for every tenant, so either all of it happens or none of it does,
+		// whether the offer is accepted by the landlord or by an acceptance policy in on_initialize.
+		fn do_accept_offer(offer_id: OfferId) -> DispatchResult {
+			frame_support::storage::with_storage_layer(|| Self::settle_offer(offer_id))
+		}
+
+		fn settle_offer(offer_id: OfferId) -> DispatchResult {
+			ensure!(Offers::<T>::contains_key(&offer_id), Error::<T>::OfferDoesNotExist);
+			let mut offer = Offers::<T>::get(&offer_id).unwrap();
+			let current_block_number =  frame_system::Pallet::<T>::block_number();
+			ensure!(current_block_number <= offer.valid_until, Error::<T>::OfferExpired);
+			ensure!(offer.offer_status == OfferStatus::Pending, Error::<T>::OfferCannotBeAccepted);
+			ensure!(offer.all_signed, Error::<T>::OfferNotFullySigned);
+			ensure!(offer.offer_start_date > current_block_number, Error::<T>::InvalidOfferStartDate); // add a buffer time maybe? start date must be at least curr + 100 blocks?
+			match Listings::<T>::get(&offer.listing_id).map(|listing| listing.offer_mode) {
+				Some(OfferMode::Sealed { reveal_end, .. }) => ensure!(current_block_number > reveal_end, Error::<T>::RevealWindowOpen),
+				Some(OfferMode::Auction { end, .. }) => ensure!(current_block_number > end, Error::<T>::AuctionNotEnded),
+				_ => {},
+			}
+			let property_id = offer.property_id;
+			ensure!(Properties::<T>::contains_key(&property_id), Error::<T>::PropertyDoesNotExist);
+			let property = Properties::<T>::get(property_id).unwrap();
+			ensure!(!Tenancies::<T>::contains_key(&property_id), Error::<T>::TenancyAlreadyExists);
+			// The agent who listed the property takes their commission out of the first payment,
+			// as long as they are still assigned to it
+			let listing_agent = Listings::<T>::get(&offer.listing_id).and_then(|listing| {
+				property.assigned_agents.iter()
+					.find(|assignment| assignment.agent_id == listing.lister)
+					.map(|assignment| (assignment.agent_id.clone(), assignment.commission))
+			});
+			let landlord_id = property.landlord_id;
+			offer.offer_status = OfferStatus::Accepted;
+			// Every tenant pays their own share of the rent straight out of their held funds
+			for (tenant_id, share) in offer.rent_shares.iter() {
+				Self::pay_rent_share(offer_id, tenant_id, &landlord_id, &listing_agent, *share)?;
+			}
+			// The security deposit stays on hold for as long as the tenancy runs.
+			let security_deposit_held = T::NativeBalance::balance_on_hold(&HoldReason::SecurityDeposit.into(), &offer.lead_tenant);
+			ensure!(security_deposit_held >= offer.security_deposit.into(), Error::<T>::SecurityDepositNotHeld);
+			// The holding deposit has done its job and goes back to the lead tenant.
+			Self::release_held(&HoldReason::HoldingDeposit.into(), &offer.lead_tenant, offer.holding_deposit.into()).map_err(|_| Error::<T>::HoldingDepositReleaseFailed)?;
+			if offer.holding_deposit > 0 {
+				Self::deposit_event(Event::HoldingDepositReturned {offer_id, to: offer.lead_tenant.clone(), amount: offer.holding_deposit});
+			}
+			if offer.security_deposit > 0 {
+				Self::deposit_event(Event::SecurityDepositRetained {offer_id, from: offer.lead_tenant.clone(), amount: offer.security_deposit});
+			}
+			Self::release_held(&HoldReason::OfferDeposit.into(), &offer.lead_tenant, offer.deposit)?;
+			Offers::<T>::insert(&offer_id, &offer);
+			Self::unschedule_expiry(offer_id, offer.valid_until);
+			Self::lapse_counter_offer(offer_id);
+			let listing_id = offer.listing_id;
+			ApplicantOffers::<T>::mutate(&offer.lead_tenant, |maybe_offers| {
+				if let Some(offers) = maybe_offers {
+					offers.retain(|id| *id != offer_id);
+				}
+			});
+			let new_tenancy = Tenancy::new(offer);
+			Tenancies::<T>::insert(&property_id, new_tenancy);
+
+			Self::deposit_event(Event::OfferAccepted {offer_id});
+			// Self::deposit_event(Event::TenancyCreated {});
+			Self::reject_competing_offers(listing_id, offer_id)?;
+			if let Some(mut listing) = Listings::<T>::get(&listing_id) {
+				listing.status = ListingStatus::Let;
+				Self::release_held(&HoldReason::ListingDeposit.into(), &listing.lister, listing.deposit)?;
+				Listings::<T>::insert(&listing_id, listing);
+			}
+			PropertyOpenListing::<T>::remove(&property_id);
+			Ok(())
+		}
+
+		// Moves a tenant's rent share from their offer hold to the landlord.
+		// The listing agent's commission on the share goes to them and the rest to the landlord.
+		fn pay_rent_share(offer_id: OfferId, tenant_id: &T::AccountId, landlord_id: &T::AccountId, listing_agent: &Option<(T::AccountId, Permill)>, share: u32) -> DispatchResult {
+			if share == 0 {
+				return Ok(());
+			}
+			ensure!(T::NativeBalance::balance_on_hold(&HoldReason::Offer.into(), tenant_id) >= share.into(), Error::<T>::RentShareNotHeld);
+			let mut rent = share;
+			if let Some((agent_id, commission)) = listing_agent {
+				let commission_amount = *commission * share;
+				if commission_amount > 0 {
+					Self::transfer_held_rent(offer_id, tenant_id, agent_id, commission_amount)?;
+					Self::deposit_event(Event::CommissionPaid {offer_id, from: tenant_id.clone(), to: agent_id.clone(), amount: commission_amount});
+					rent = share.saturating_sub(commission_amount);
+				}
+			}
+			if rent > 0 {
+				Self::transfer_held_rent(offer_id, tenant_id, landlord_id, rent)?;
+				Self::deposit_event(Event::RentPaid {offer_id, from: tenant_id.clone(), to: landlord_id.clone(), amount: rent});
+			}
+			Ok(())
+		}
+
+		fn transfer_held_rent(offer_id: OfferId, from: &T::AccountId, to: &T::AccountId, amount: u32) -> DispatchResult {
+			T::NativeBalance::transfer_on_hold(
+				&HoldReason::Offer.into(),
+				from,
+				to,
+				amount.into(),
+				Precision::Exact,
+				Restriction::Free,
+				Fortitude::Polite,
+			).map_err(|_| Error::<T>::RentTransferFailed)?;
+			Ok(())
+		}
+
+		// Creates a pending offer on a listing and puts the lead tenant's rent share on hold.
+		fn do_submit_offer(applicant_id: T::AccountId, offer_listing: Listing<T>, offer_price: u32, offer_start_date: BlockNumberFor<T>, offer_end_date: BlockNumberFor<T>, prospective_tenant_ids: BoundedVec<T::AccountId, T::MaxNumberOfTenants>, valid_until: BlockNumberFor<T>, custom_shares: Option<BoundedVec<u32, T::MaxNumberOfTenants>>) -> Result<OfferId, DispatchError> {
+			let listing_id = offer_listing.listing_id;
+			ensure!(offer_listing.is_open(), Error::<T>::ListingNotOpen);
+			if offer_listing.attributes.viewing_required {
+				ensure!(ViewingAttendance::<T>::contains_key(&listing_id, &applicant_id), Error::<T>::ViewingNotAttended);
+			}
+			ensure!(!ApplicantListingOffers::<T>::contains_key(&applicant_id, &listing_id), Error::<T>::AlreadyOfferedOnListing);
+			Self::ensure_valid_offer_terms(&applicant_id, &offer_listing, offer_start_date, offer_end_date, &prospective_tenant_ids, valid_until)?;
+			let rent_shares = Self::split_rent(&applicant_id, &prospective_tenant_ids, offer_price, custom_shares)?;
+			let lead_share = rent_shares.iter().find(|(id, _)| *id == applicant_id).map(|(_, share)| *share).unwrap_or_default();
+			let offer_count = OfferCounter::<T>::get().unwrap_or_default();
+			ensure!(offer_count.checked_add(1).is_some(), Error::<T>::TooManyOffers); // change to storage overflow
+			let new_offer_id = offer_count + 1;
+
+			let number_of_prospective_tenants = prospective_tenant_ids.len();
+			let prospective_tenant_signatures = Self::initial_signatures(&applicant_id, &prospective_tenant_ids)?;
+			let all_signed = if number_of_prospective_tenants == 1 { true } else { false };
+			let deposit = T::OfferDeposit::get();
+			let new_offer = Offer::new(new_offer_id, listing_id, offer_listing.property_id, offer_price, offer_listing.holding_deposit, offer_listing.security_deposit, offer_start_date, offer_end_date, applicant_id.clone(), prospective_tenant_ids.clone(), prospective_tenant_signatures, rent_shares, valid_until, all_signed, deposit);
+			// new_offer.clone() does not work??
+			// let new_offer2 = Offer::new(new_offer_id, listing_id, offer_listing.property_id, offer_price, offer_start_date, offer_end_date, prospective_tenant_ids.clone());
+			let mut offers_on_listing = ListingOffers::<T>::get(&listing_id).unwrap_or(BoundedVec::new());
+			offers_on_listing.try_push(new_offer_id).map_err(|_| Error::<T>::TooManyOffersOnListing)?;
+
+			let mut all_applicant_offers = ApplicantOffers::<T>::get(&applicant_id).unwrap_or(BoundedVec::new());
+			all_applicant_offers.try_push(new_offer_id).map_err(|_| Error::<T>::MaxOffersForApplicantReached)?;
+
+			let expiry_block = valid_until.saturating_add(One::one());
+			let mut expiring_offers = OfferExpiries::<T>::get(&expiry_block).unwrap_or(BoundedVec::new());
+			expiring_offers.try_push(new_offer_id).map_err(|_| Error::<T>::TooManyOffersExpiringInBlock)?;
+
+
+			ListingOffers::<T>::insert(&listing_id, &offers_on_listing);
+			ApplicantOffers::<T>::insert(&applicant_id, &all_applicant_offers);
+			ApplicantListingOffers::<T>::insert(&applicant_id, &listing_id, new_offer_id);
+			Offers::<T>::insert(&new_offer_id, &new_offer);
+			OfferExpiries::<T>::insert(&expiry_block, &expiring_offers);
+			OfferCounter::<T>::put(new_offer_id);
+			Self::refresh_listing_status(listing_id);
+			Self::schedule_auto_acceptance(&new_offer)?;
+
+			Self::hold_funds(&HoldReason::Offer.into(), &applicant_id, lead_share, Error::<T>::InsufficientFundsForOffer)?;
+			Self::hold_funds(&HoldReason::HoldingDeposit.into(), &applicant_id, offer_listing.holding_deposit, Error::<T>::InsufficientFundsForDeposits)?;
+			Self::hold_funds(&HoldReason::SecurityDeposit.into(), &applicant_id, offer_listing.security_deposit, Error::<T>::InsufficientFundsForDeposits)?;
+			Self::hold_storage_deposit(&HoldReason::OfferDeposit.into(), &applicant_id, deposit)?;
+
+			Self::deposit_event(Event::NewOfferSubmitted { listing_id, offer_price, offer_start_date, offer_end_date, prospective_tenant_ids });
+			Ok(new_offer_id)
+		}
+
+		// Checks the terms of an offer against its listing, shared by new and amended offers.
+		fn ensure_valid_offer_terms(applicant_id: &T::AccountId, offer_listing: &Listing<T>, offer_start_date: BlockNumberFor<T>, offer_end_date: BlockNumberFor<T>, prospective_tenant_ids: &BoundedVec<T::AccountId, T::MaxNumberOfTenants>, valid_until: BlockNumberFor<T>) -> DispatchResult {
+			let current_block_number =  frame_system::Pallet::<T>::block_number();
+			ensure!(valid_until > current_block_number, Error::<T>::OfferValidUntilMustBeFuture); // Maybe add min? Don't want one block offers
+			ensure!(offer_start_date >= current_block_number
+					&& offer_start_date < offer_end_date
+					&& offer_start_date >= offer_listing.availability_date, Error::<T>::InvalidOfferStartDate);
+
+			ensure!(prospective_tenant_ids.len() > 0, Error::<T>::TenantsIdsCannotBeEmpty);
+			// ensure!(prospective_tenant_ids.len() <= T::MaxNumberOfTenants::get(), Error::<T>::TooManyTenants); Not necessary?
+			ensure!(&prospective_tenant_ids.iter().all(|applicant_id| VerifiedApplicants::<T>::contains_key(&applicant_id)), Error::<T>::AllApplicantsMustBeVerified);
+			ensure!(prospective_tenant_ids.contains(applicant_id), Error::<T>::LeadTenantMustBeProspectiveTenant);
+			// Rent shares and signatures are looked up by account, so each tenant can only be on the offer once
+			ensure!(prospective_tenant_ids.iter().enumerate().all(|(i, tenant_id)| !prospective_tenant_ids[..i].contains(tenant_id)), Error::<T>::DuplicateTenantIds);
+			let attributes = &offer_listing.attributes;
+			if let Some(max_occupants) = attributes.max_occupants {
+				ensure!(prospective_tenant_ids.len() as u32 <= max_occupants, Error::<T>::TooManyOccupants);
+			}
+			let term = offer_end_date.saturating_sub(offer_start_date);
+			if let Some(min_term) = attributes.min_term {
+				ensure!(term >= min_term, Error::<T>::TermTooShort);
+			}
+			if let Some(max_term) = attributes.max_term {
+				ensure!(term <= max_term, Error::<T>::TermTooLong);
+			}
+			ensure!(Properties::<T>::contains_key(&offer_listing.property_id), Error::<T>::PropertyDoesNotExist);
+			let property = Properties::<T>::get(offer_listing.property_id).unwrap();
+			// The lister may be an agent who has since been removed from the property
+			let is_excluded = |who: &T::AccountId| property.is_managed_by(who) || offer_listing.lister == *who;
+			ensure!(!is_excluded(applicant_id) && !prospective_tenant_ids.iter().any(|tenant_id| is_excluded(tenant_id)), Error::<T>::LandlordCannotOfferOnOwnProperty);
+			Ok(())
+		}
+
+		// Moves an offer to the expiry schedule of its new valid_until.
+		fn reschedule_expiry(offer_id: OfferId, old_valid_until: BlockNumberFor<T>, new_valid_until: BlockNumberFor<T>) -> DispatchResult {
+			Self::unschedule_expiry(offer_id, old_valid_until);
+			let expiry_block = new_valid_until.saturating_add(One::one());
+			let mut expiring_offers = OfferExpiries::<T>::get(&expiry_block).unwrap_or(BoundedVec::new());
+			expiring_offers.try_push(offer_id).map_err(|_| Error::<T>::TooManyOffersExpiringInBlock)?;
+			OfferExpiries::<T>::insert(&expiry_block, &expiring_offers);
+			Ok(())
+		}
+
+		// The leading bid on an auction is binding, pulling it would leave the auction without a winner.
+		fn ensure_not_leading_bid(offer: &Offer<T>) -> DispatchResult {
+			ensure!(AuctionLeaders::<T>::get(&offer.listing_id) != Some(offer.offer_id), Error::<T>::LeadingBidIsBinding);
+			Ok(())
+		}
+
+		// Frees an offer's slot in the expiry schedule once it no longer needs expiring.
+		fn unschedule_expiry(offer_id: OfferId, valid_until: BlockNumberFor<T>) {
+			OfferExpiries::<T>::mutate(&valid_until.saturating_add(One::one()), |maybe_offers| {
+				if let Some(offers) = maybe_offers {
+					offers.retain(|id| *id != offer_id);
+				}
+			});
+		}
+
+		// Releases the rent share of every tenant who has signed the offer, signing is what holds it.
+		fn release_offer_funds(offer: &Offer<T>) -> DispatchResult {
+			for (tenant_id, _) in offer.prospective_tenant_signatures.iter().filter(|(_, signed)| *signed) {
+				let share = offer.rent_share_of(tenant_id).unwrap_or_default();
+				Self::release_held(&HoldReason::Offer.into(), tenant_id, share.into())?;
+			}
+			Ok(())
+		}
+
+		// Puts funds on hold, failing with the given error if the account can't cover them.
+		// Nothing to do for a zero amount, e.g. listings that don't ask for a deposit.
+		fn hold_funds(reason: &T::RuntimeHoldReason, who: &T::AccountId, amount: u32, error: Error<T>) -> DispatchResult {
+			if amount == 0 {
+				return Ok(());
+			}
+			T::NativeBalance::hold(reason, who, amount.into()).map_err(|_| error)?;
+			Ok(())
+		}
+
+		// The landlord of the listed property and their agents with listing permissions can manage it.
+		fn ensure_can_manage_listing(who: &T::AccountId, listing: &Listing<T>) -> DispatchResult {
+			ensure!(Properties::<T>::contains_key(&listing.property_id), Error::<T>::PropertyDoesNotExist);
+			let property = Properties::<T>::get(listing.property_id).unwrap();
+			ensure!(property.is_authorised(who, AgentPermission::ManageListings), Error::<T>::Unauthorized);
+			Ok(())
+		}
+
+		// Puts a listing's or offer's storage deposit on hold.
+		fn hold_storage_deposit(reason: &T::RuntimeHoldReason, who: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+			if amount.is_zero() {
+				return Ok(());
+			}
+			T::NativeBalance::hold(reason, who, amount).map_err(|_| Error::<T>::InsufficientFundsForStorageDeposit)?;
+			Ok(())
+		}
+
+		// Releases what an offer, listing or request put on hold back to the account.
+		// The hold is shared with the account's other offers and listings, so never more than that amount.
+		fn release_held(reason: &T::RuntimeHoldReason, who: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+			if amount.is_zero() {
+				return Ok(());
+			}
+			T::NativeBalance::release(reason, who, amount, Precision::BestEffort)?;
+			Ok(())
+		}
+
+		// Splits offer_price between the tenants. Without custom shares everyone pays an even share
+		// and the lead tenant picks up the remainder, e.g. 1000 between 3 is 334, 333 and 333.
+		fn split_rent(lead_tenant: &T::AccountId, prospective_tenant_ids: &BoundedVec<T::AccountId, T::MaxNumberOfTenants>, offer_price: u32, custom_shares: Option<BoundedVec<u32, T::MaxNumberOfTenants>>) -> Result<BoundedVec<(T::AccountId, u32), T::MaxNumberOfTenants>, DispatchError> {
+			let shares: Vec<(T::AccountId, u32)> = match custom_shares {
+				Some(custom_shares) => {
+					ensure!(custom_shares.len() == prospective_tenant_ids.len(), Error::<T>::RentSharesDoNotMatchTenants);
+					let total = custom_shares.iter().try_fold(0u32, |total, share| total.checked_add(*share));
+					ensure!(total == Some(offer_price), Error::<T>::RentSharesMustSumToOfferPrice);
+					prospective_tenant_ids.clone().into_iter().zip(custom_shares.into_iter()).collect()
+				},
+				None => {
+					let number_of_tenants = prospective_tenant_ids.len() as u32;
+					ensure!(number_of_tenants > 0, Error::<T>::TenantsIdsCannotBeEmpty);
+					let even_share = offer_price / number_of_tenants;
+					let remainder = offer_price - even_share * number_of_tenants;
+					prospective_tenant_ids.clone().into_iter().map(|t_id| if t_id == *lead_tenant {(t_id, even_share + remainder)} else {(t_id, even_share)}).collect()
+				},
+			};
+			let rent_shares = BoundedVec::try_from(shares).map_err(|_| Error::<T>::TooManyTenants)?;
+			Ok(rent_shares)
+		}
+
+		// Marks a counter offer that was never answered as lapsed once its offer is settled or closed.
+		fn lapse_counter_offer(offer_id: OfferId) {
+			OfferNegotiations::<T>::mutate(&offer_id, |maybe_rounds| {
+				if let Some(rounds) = maybe_rounds {
+					rounds.iter_mut()
+						.filter(|round| round.status == CounterOfferStatus::Open)
+						.for_each(|round| round.status = CounterOfferStatus::Lapsed);
+				}
+			});
+		}
+
+		// Only the lead tenant has signed a new offer, unless they are the only tenant on it.
+		fn initial_signatures(lead_tenant: &T::AccountId, prospective_tenant_ids: &BoundedVec<T::AccountId, T::MaxNumberOfTenants>) -> Result<BoundedVec<(T::AccountId, bool), T::MaxNumberOfTenants>, DispatchError> {
+			let number_of_prospective_tenants = prospective_tenant_ids.len();
+			let init_ids_and_sigs: Vec<(T::AccountId, bool)> = prospective_tenant_ids.clone().into_iter().map(|t_id| if number_of_prospective_tenants == 1 {(t_id, true)} else {if t_id == *lead_tenant {(t_id, true)} else {(t_id, false)}}).collect();
+			let prospective_tenant_signatures = BoundedVec::try_from(init_ids_and_sigs).map_err(|_| Error::<T>::TooManyTenants)?; // should not be possible to err here
+			Ok(prospective_tenant_signatures)
+		}
+
+		// Expires the offers scheduled for this block that are still pending.
+		// Offers are taken off the schedule when they are closed or settled, the status check is a safeguard.
+		fn expire_offers(block_number: BlockNumberFor<T>) -> Weight {
+			let expiring_offers = OfferExpiries::<T>::take(&block_number).unwrap_or_default();
+			let mut weight = T::DbWeight::get().reads_writes(1, 1);
+			for offer_id in expiring_offers {
+				weight = weight.saturating_add(T::DbWeight::get().reads(1));
+				let Some(mut offer) = Offers::<T>::get(&offer_id) else { continue };
+				if offer.offer_status != OfferStatus::Pending || offer.valid_until >= block_number {
+					continue;
+				}
+				// A failed close must not leave the offer half released, so it is rolled back as a whole
+				if frame_support::storage::with_storage_layer(|| Self::close_offer(&mut offer, OfferStatus::Expired)).is_ok() {
+					Self::deposit_event(Event::OfferExpired {offer_id});
+				}
+				weight = weight.saturating_add(Self::close_offer_weight());
+			}
+			weight
+		}
+
+		// Runs whatever is due on the listings scheduled for this block.
+		fn process_listing_deadlines(block_number: BlockNumberFor<T>) -> Weight {
+			let due_listings = ListingDeadlines::<T>::take(&block_number).unwrap_or_default();
+			let mut weight = T::DbWeight::get().reads_writes(1, 1);
+			for listing_id in due_listings {
+				weight = weight.saturating_add(T::DbWeight::get().reads(1));
+				let Some(listing) = Listings::<T>::get(&listing_id) else { continue };
+				match listing.offer_mode {
+					OfferMode::Sealed { reveal_end, .. } if reveal_end < block_number => {
+						weight = weight.saturating_add(Self::expire_unrevealed_bids(listing_id));
+					},
+					OfferMode::Auction { end, .. } if end < block_number => {
+						weight = weight.saturating_add(Self::settle_auction(&listing));
+					},
+					_ => {},
+				}
+				weight = weight.saturating_add(Self::apply_acceptance_policy(&listing, block_number));
+			}
+			weight
+		}
+
+		// Turns the winning bid into a tenancy through the same path as accept_offer.
+		fn settle_auction(listing: &Listing<T>) -> Weight {
+			let listing_id = listing.listing_id;
+			let weight = T::DbWeight::get().reads(1).saturating_add(Self::settle_offer_weight());
+			if !listing.is_open() {
+				return weight;
+			}
+			match AuctionLeaders::<T>::take(&listing_id) {
+				Some(offer_id) if Self::do_accept_offer(offer_id).is_ok() => {
+					Self::deposit_event(Event::AuctionSettled {listing_id, offer_id});
+				},
+				_ => Self::deposit_event(Event::AuctionEndedWithoutWinner {listing_id}),
+			}
+			weight
+		}
+
+		// Picks the offer the listing's acceptance policy calls for and settles it like accept_offer would.
+		// If the best offer can't be settled the next one in line is tried, up to MaxAcceptanceAttempts of them.
+		fn apply_acceptance_policy(listing: &Listing<T>, block_number: BlockNumberFor<T>) -> Weight {
+			let mut weight = Weight::zero();
+			// Withdrawn or already let listings have nothing left to accept
+			if !listing.is_open() {
+				return weight;
+			}
+			let due = match listing.acceptance_policy {
+				AcceptancePolicy::Manual => false,
+				AcceptancePolicy::HighestAfterDeadline { deadline } => deadline < block_number,
+				AcceptancePolicy::FirstAtAsking => true,
+			};
+			if !due {
+				return weight;
+			}
+
+			let offers_on_listing = ListingOffers::<T>::get(&listing.listing_id).unwrap_or_default();
+			weight = weight.saturating_add(T::DbWeight::get().reads(1 + offers_on_listing.len() as u64));
+			let mut candidates: Vec<Offer<T>> = offers_on_listing.into_iter()
+				.filter_map(|offer_id| Offers::<T>::get(&offer_id))
+				.filter(|offer| offer.offer_status == OfferStatus::Pending
+					&& offer.all_signed
+					&& offer.offer_price >= listing.rental_price
+					&& offer.valid_until >= block_number
+					&& offer.offer_start_date > block_number)
+				.collect();
+			// Highest first, the sort is stable so ties go to the earliest offer
+			if let AcceptancePolicy::HighestAfterDeadline { .. } = listing.acceptance_policy {
+				candidates.sort_by(|a, b| b.offer_price.cmp(&a.offer_price));
+			}
+
+			for offer in candidates.into_iter().take(T::MaxAcceptanceAttempts::get() as usize) {
+				weight = weight.saturating_add(Self::settle_offer_weight());
+				if Self::do_accept_offer(offer.offer_id).is_ok() {
+					Self::deposit_event(Event::OfferAutoAccepted {listing_id: listing.listing_id, offer_id: offer.offer_id});
+					return weight;
+				}
+			}
+			// The deadline only comes round once, from here on the landlord accepts offers themselves.
+			// FirstAtAsking listings are checked again whenever an offer at the asking price is signed.
+			if let AcceptancePolicy::HighestAfterDeadline { .. } = listing.acceptance_policy {
+				Self::deposit_event(Event::NoOfferAutoAccepted {listing_id: listing.listing_id});
+			}
+			weight
+		}
+
+		// FirstAtAsking listings accept in the block after an offer at the asking price is fully signed.
+		fn schedule_auto_acceptance(offer: &Offer<T>) -> DispatchResult {
+			if !offer.all_signed {
+				return Ok(());
+			}
+			let Some(listing) = Listings::<T>::get(&offer.listing_id) else { return Ok(()) };
+			if listing.acceptance_policy == AcceptancePolicy::FirstAtAsking && offer.offer_price >= listing.rental_price {
+				let current_block_number =  frame_system::Pallet::<T>::block_number();
+				Self::schedule_listing_deadline(listing.listing_id, current_block_number.saturating_add(One::one()))?;
+			}
+			Ok(())
+		}
+
+		// Keeps an open listing's status in line with whether it has any pending offers.
+		fn refresh_listing_status(listing_id: ListingId) {
+			let has_offers = ListingOffers::<T>::get(&listing_id).map_or(false, |offers| !offers.is_empty());
+			Listings::<T>::mutate(&listing_id, |maybe_listing| {
+				if let Some(listing) = maybe_listing.as_mut().filter(|listing| listing.is_open()) {
+					listing.status = if has_offers { ListingStatus::UnderOffer } else { ListingStatus::Open };
+				}
+			});
+		}
+
+		fn schedule_listing_deadline(listing_id: ListingId, block_number: BlockNumberFor<T>) -> DispatchResult {
+			let mut due_listings = ListingDeadlines::<T>::get(&block_number).unwrap_or(BoundedVec::new());
+			if due_listings.contains(&listing_id) {
+				return Ok(());
+			}
+			due_listings.try_push(listing_id).map_err(|_| Error::<T>::TooManyListingDeadlinesInBlock)?;
+			ListingDeadlines::<T>::insert(&block_number, due_listings);
+			Ok(())
+		}
+
+		// Clears the commitments nobody revealed in time and releases what they had on hold.
+		// Bounded by MaxOffersPerListing through ListingSealedBidders.
+		fn expire_unrevealed_bids(listing_id: ListingId) -> Weight {
+			let sealed_bidders = ListingSealedBidders::<T>::take(&listing_id).unwrap_or_default();
+			let mut weight = T::DbWeight::get().reads_writes(1, 1);
+			for applicant_id in sealed_bidders {
+				if let Some(sealed_bid) = SealedBids::<T>::take(&listing_id, &applicant_id) {
+					let _ = Self::release_held(&HoldReason::SealedBid.into(), &applicant_id, sealed_bid.max_price.into());
+				}
+				weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 2));
+				Self::deposit_event(Event::SealedOfferExpired {listing_id, applicant_id});
+			}
+			weight
+		}
+
+		fn remove_sealed_bid(listing_id: ListingId, applicant_id: &T::AccountId) -> DispatchResult {
+			let sealed_bid = SealedBids::<T>::take(&listing_id, applicant_id);
+			ListingSealedBidders::<T>::mutate(&listing_id, |maybe_bidders| {
+				if let Some(bidders) = maybe_bidders {
+					bidders.retain(|bidder| bidder != applicant_id);
+				}
+			});
+			let max_price = sealed_bid.map_or(0, |sealed_bid| sealed_bid.max_price);
+			Self::release_held(&HoldReason::SealedBid.into(), applicant_id, max_price.into())
+		}
+
+		// Rejects every other pending offer on a listing once one of them has been accepted.
+		// Bounded by MaxOffersPerListing since that is the most ListingOffers can hold.
+		fn reject_competing_offers(listing_id: ListingId, accepted_offer_id: OfferId) -> DispatchResult {
+			let offers_on_listing = ListingOffers::<T>::take(&listing_id).unwrap_or_default();
+			for offer_id in offers_on_listing.into_iter().filter(|id| *id != accepted_offer_id) {
+				let Some(mut offer) = Offers::<T>::get(&offer_id) else { continue };
+				if offer.offer_status != OfferStatus::Pending {
+					continue;
+				}
+				Self::close_offer(&mut offer, OfferStatus::Rejected)?;
+				Self::deposit_event(Event::CompetingOfferRejected {offer_id, accepted_offer_id});
+			}
+			Ok(())
+		}
 	}
 }
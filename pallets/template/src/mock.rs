@@ -1,5 +1,5 @@
 use crate as pallet_template;
-use frame_support::traits::{ConstU16, ConstU32, ConstU64, ConstU128};
+use frame_support::{parameter_types, traits::{ConstU16, ConstU32, ConstU64, ConstU128}};
 use sp_core::H256;
 use sp_runtime::{
 	traits::{BlakeTwo256, IdentityLookup},
@@ -55,20 +55,37 @@ impl pallet_balances::Config for Test {
 	type MaxLocks = ConstU32<10>;
 	type MaxReserves = ();
 	type ReserveIdentifier = [u8; 8];
-	type RuntimeHoldReason = ();
-	type FreezeIdentifier = RuntimeFreezeReason;
+	type RuntimeHoldReason = RuntimeHoldReason;
+	type FreezeIdentifier = ();
 	type MaxHolds = ConstU32<10>;
 	type MaxFreezes = ConstU32<10>;
 }
 
+parameter_types! {
+	// Zero unless a test sets them, so most tests don't have to fund storage deposits
+	pub static ListingDeposit: Balance = 0;
+	pub static OfferDeposit: Balance = 0;
+}
+
 impl pallet_template::Config for Test {
 	type RuntimeEvent = RuntimeEvent;
 	type MaxNumberOfTenants = ConstU32<6>;
 	type MaxNumberOfAgents = ConstU32<6>;
 	type MaxOffersPerListing = ConstU32<20>;
 	type MaxOffersPerApplicant = ConstU32<5>;
+	type MaxOffersExpiringPerBlock = ConstU32<20>;
+	type MaxCounterOffers = ConstU32<5>;
+	type MaxListingDeadlinesPerBlock = ConstU32<10>;
+	type MaxViewingSlotsPerListing = ConstU32<10>;
+	type MaxAcceptanceAttempts = ConstU32<3>;
+	type DepositReturnPeriod = ConstU64<10>;
+	type ListingDeposit = ListingDeposit;
+	type OfferDeposit = OfferDeposit;
+	type RegistrarOrigin = frame_system::EnsureRoot<u64>;
+	type RegistrationDeposit = ConstU128<100>;
+	type MaxPendingRegistrations = ConstU32<10>;
 	type NativeBalance = Balances;
-	type RuntimeFreezeReason = RuntimeFreezeReason;
+	type RuntimeHoldReason = RuntimeHoldReason;
 }
 
 // Build genesis storage according to the mock runtime.
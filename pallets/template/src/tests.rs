@@ -1,6 +1,6 @@
-use crate::{mock::*, Error, Event, Property};
-use frame_support::{assert_noop, assert_ok, pallet_prelude::DispatchError, traits::{fungible, tokens::{fungible::freeze::Inspect, Preservation::Expendable}}, BoundedVec};
-use sp_runtime::TokenError::Frozen;
+use crate::{mock::*, Error, Event, Property, Offers, OfferStatus, ListingOffers, ApplicantOffers, OfferNegotiations, CounterOfferStatus, OfferMode, AcceptancePolicy, Tenancies, OfferExpiries, HoldReason, Listings, ListingStatus, ListingAttributes, ListingFilter, Furnishing, ViewingSlots, AgentPermissions};
+use frame_support::{assert_noop, assert_ok, pallet_prelude::DispatchError, traits::{fungible, fungible::InspectHold, Hooks, tokens::{Precision, Preservation::Expendable}}, BoundedVec};
+use sp_runtime::{traits::{BlakeTwo256, Hash}, Permill, TokenError::FundsUnavailable};
 
 
 #[test]
@@ -40,7 +40,7 @@ fn can_register_property() {
 }
 
 #[test]
-fn balance_is_frozen_on_submit_offer() {
+fn balance_is_held_on_submit_offer() {
 	new_test_ext().execute_with(|| {
 		// Go past genesis block so events get deposited
 		System::set_block_number(1);
@@ -48,14 +48,14 @@ fn balance_is_frozen_on_submit_offer() {
 		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1000);
 		assert_eq!(Balances::free_balance(&1), 1000);
 		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
-		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
 		let mut tenants = BoundedVec::new();
 		tenants.try_push((1)).unwrap();
-		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants, 100));
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants, 100, None));
 
 		assert_eq!(
 			<Balances as fungible::Mutate<_>>::transfer(&1, &2, 101, Expendable),
-			Err(DispatchError::Token(Frozen))
+			Err(DispatchError::Token(FundsUnavailable))
 		);
 	});
 }
@@ -70,18 +70,20 @@ fn funds_are_transferred_on_offer_accepted() {
 		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 102));
 		let _ = <Balances as fungible::Mutate<_>>::mint_into(&101, 1000);
 		assert_eq!(Balances::free_balance(&101), 1000);
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&102, 1000);
 		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
-		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
 		let mut tenants = BoundedVec::new();
 		tenants.try_push((101)).unwrap();
 		tenants.try_push((102)).unwrap();
-		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(101), 1, 900, 51, 101, tenants, 100));
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(101), 1, 900, 51, 101, tenants, 100, None));
 		assert_ok!(RealEstate::sign_offer(RuntimeOrigin::signed(102), 1));
 
 		assert_eq!(Balances::free_balance(&2), 0);
 		let p = Property {
 			 property_id: 1,
 			 landlord_id: 2,
+			 assigned_agents: BoundedVec::new(),
 			 address: sp_core::H256::repeat_byte(1),
 			 postal_code: sp_core::H256::repeat_byte(1),
 		};
@@ -89,7 +91,1143 @@ fn funds_are_transferred_on_offer_accepted() {
 		assert_eq!(Balances::free_balance(&2), 0);
 		assert_ok!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 1));
 		assert_eq!(Balances::free_balance(&2), 900);
+		assert_eq!(Listings::<Test>::get(1).unwrap().status, ListingStatus::Let);
 	});
 }
 
-// Landlord can also be an applicant, but they should not be able to offer on their own property.
\ No newline at end of file
+#[test]
+fn funds_are_released_on_offer_withdrawn() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1000);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(1).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants, 100, None));
+
+		assert_noop!(RealEstate::withdraw_offer(RuntimeOrigin::signed(3), 1), Error::<Test>::Unauthorized);
+		assert_ok!(RealEstate::withdraw_offer(RuntimeOrigin::signed(1), 1));
+		System::assert_last_event(Event::OfferWithdrawn { offer_id: 1, withdrawn_by: 1 }.into());
+
+		assert_eq!(Offers::<Test>::get(1).unwrap().offer_status, OfferStatus::Cancelled);
+		assert!(ListingOffers::<Test>::get(1).unwrap().is_empty());
+		assert!(ApplicantOffers::<Test>::get(1).unwrap().is_empty());
+		assert_ok!(<Balances as fungible::Mutate<_>>::transfer(&1, &2, 101, Expendable));
+		assert_noop!(RealEstate::withdraw_offer(RuntimeOrigin::signed(1), 1), Error::<Test>::OfferNotPending);
+	});
+}
+
+#[test]
+fn funds_are_released_on_offer_rejected() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1000);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(1).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants, 100, None));
+
+		assert_noop!(RealEstate::reject_offer(RuntimeOrigin::signed(1), 1, None), Error::<Test>::Unauthorized);
+		assert_ok!(RealEstate::reject_offer(RuntimeOrigin::signed(2), 1, Some(3)));
+		System::assert_last_event(Event::OfferRejected { offer_id: 1, reason_code: Some(3) }.into());
+
+		assert_eq!(Offers::<Test>::get(1).unwrap().offer_status, OfferStatus::Rejected);
+		assert!(ApplicantOffers::<Test>::get(1).unwrap().is_empty());
+		assert_ok!(<Balances as fungible::Mutate<_>>::transfer(&1, &2, 101, Expendable));
+	});
+}
+
+#[test]
+fn competing_offers_are_rejected_on_offer_accepted() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 3));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1000);
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&3, 1000);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(1).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants, 100, None));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(3).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(3), 1, 950, 51, 101, tenants, 100, None));
+
+		assert_ok!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 2));
+		System::assert_last_event(Event::CompetingOfferRejected { offer_id: 1, accepted_offer_id: 2 }.into());
+
+		assert_eq!(Offers::<Test>::get(1).unwrap().offer_status, OfferStatus::Rejected);
+		assert!(ListingOffers::<Test>::get(1).is_none());
+		assert!(ApplicantOffers::<Test>::get(1).unwrap().is_empty());
+		assert!(ApplicantOffers::<Test>::get(3).unwrap().is_empty());
+		assert_ok!(<Balances as fungible::Mutate<_>>::transfer(&1, &2, 101, Expendable));
+	});
+}
+
+#[test]
+fn offers_expire_after_valid_until() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1000);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(1).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants, 40, None));
+
+		System::set_block_number(40);
+		RealEstate::on_initialize(40);
+		assert_eq!(Offers::<Test>::get(1).unwrap().offer_status, OfferStatus::Pending);
+
+		System::set_block_number(41);
+		RealEstate::on_initialize(41);
+		System::assert_last_event(Event::OfferExpired { offer_id: 1 }.into());
+		assert_eq!(Offers::<Test>::get(1).unwrap().offer_status, OfferStatus::Expired);
+		assert!(ListingOffers::<Test>::get(1).unwrap().is_empty());
+		assert!(ApplicantOffers::<Test>::get(1).unwrap().is_empty());
+		assert_ok!(<Balances as fungible::Mutate<_>>::transfer(&1, &2, 101, Expendable));
+	});
+}
+
+#[test]
+fn counter_offer_updates_terms_and_hold() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1000);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(1).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants, 100, None));
+
+		assert_noop!(RealEstate::accept_counter_offer(RuntimeOrigin::signed(1), 1, None), Error::<Test>::NoOpenCounterOffer);
+		assert_ok!(RealEstate::propose_counter_offer(RuntimeOrigin::signed(2), 1, 950, 60, 120));
+		assert_noop!(RealEstate::propose_counter_offer(RuntimeOrigin::signed(2), 1, 975, 60, 120), Error::<Test>::CounterOfferAlreadyOpen);
+		assert_ok!(RealEstate::decline_counter_offer(RuntimeOrigin::signed(1), 1));
+		assert_ok!(RealEstate::propose_counter_offer(RuntimeOrigin::signed(2), 1, 950, 60, 120));
+		assert_ok!(RealEstate::accept_counter_offer(RuntimeOrigin::signed(1), 1, None));
+		System::assert_last_event(Event::CounterOfferAccepted { offer_id: 1, round: 1 }.into());
+
+		let offer = Offers::<Test>::get(1).unwrap();
+		assert_eq!((offer.offer_price, offer.offer_start_date, offer.offer_end_date), (950, 60, 120));
+		let rounds = OfferNegotiations::<Test>::get(1).unwrap();
+		assert_eq!(rounds[0].status, CounterOfferStatus::Declined);
+		assert_eq!(rounds[1].status, CounterOfferStatus::Accepted);
+		assert_eq!(
+			<Balances as fungible::Mutate<_>>::transfer(&1, &2, 51, Expendable),
+			Err(DispatchError::Token(FundsUnavailable))
+		);
+	});
+}
+
+#[test]
+fn each_tenant_pays_their_rent_share() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		for applicant_id in [101, 102, 103] {
+			assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), applicant_id));
+			let _ = <Balances as fungible::Mutate<_>>::mint_into(&applicant_id, 1000);
+		}
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(101).unwrap();
+		tenants.try_push(102).unwrap();
+		tenants.try_push(103).unwrap();
+		let bad_shares = BoundedVec::try_from(vec![400, 300, 200]).unwrap();
+		assert_noop!(
+			RealEstate::submit_offer(RuntimeOrigin::signed(101), 1, 1000, 51, 101, tenants.clone(), 100, Some(bad_shares)),
+			Error::<Test>::RentSharesMustSumToOfferPrice
+		);
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(101), 1, 1000, 51, 101, tenants, 100, None));
+		assert_eq!(Offers::<Test>::get(1).unwrap().rent_shares.into_inner(), vec![(101, 334), (102, 333), (103, 333)]);
+
+		assert_ok!(RealEstate::sign_offer(RuntimeOrigin::signed(102), 1));
+		assert_eq!(
+			<Balances as fungible::Mutate<_>>::transfer(&102, &2, 668, Expendable),
+			Err(DispatchError::Token(FundsUnavailable))
+		);
+		assert_ok!(RealEstate::sign_offer(RuntimeOrigin::signed(103), 1));
+		assert_ok!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 1));
+
+		assert_eq!(Balances::free_balance(&2), 1000);
+		assert_eq!(Balances::free_balance(&101), 666);
+		assert_eq!(Balances::free_balance(&102), 667);
+		assert_eq!(Balances::free_balance(&103), 667);
+	});
+}
+
+#[test]
+fn applicant_can_only_make_one_offer_per_listing() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1000);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(1).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants.clone(), 100, None));
+		assert_eq!(RealEstate::get_applicant_offer(1, 1).unwrap().offer_id, 1);
+		assert_noop!(
+			RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 950, 51, 101, tenants.clone(), 100, None),
+			Error::<Test>::AlreadyOfferedOnListing
+		);
+
+		assert_ok!(RealEstate::withdraw_offer(RuntimeOrigin::signed(1), 1));
+		assert!(RealEstate::get_applicant_offer(1, 1).is_none());
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 950, 51, 101, tenants, 100, None));
+		assert_eq!(RealEstate::get_applicant_offer(1, 1).unwrap().offer_id, 2);
+	});
+}
+
+#[test]
+fn amending_an_offer_resets_co_tenant_signatures() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		for applicant_id in [101, 102] {
+			assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), applicant_id));
+			let _ = <Balances as fungible::Mutate<_>>::mint_into(&applicant_id, 1000);
+		}
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(101).unwrap();
+		tenants.try_push(102).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(101), 1, 900, 51, 101, tenants.clone(), 100, None));
+		assert_ok!(RealEstate::sign_offer(RuntimeOrigin::signed(102), 1));
+		assert!(Offers::<Test>::get(1).unwrap().all_signed);
+
+		assert_noop!(
+			RealEstate::amend_offer(RuntimeOrigin::signed(102), 1, 1000, 51, 101, tenants.clone(), 100, None),
+			Error::<Test>::Unauthorized
+		);
+		assert_ok!(RealEstate::amend_offer(RuntimeOrigin::signed(101), 1, 1000, 51, 101, tenants, 120, None));
+
+		let offer = Offers::<Test>::get(1).unwrap();
+		assert_eq!((offer.offer_price, offer.valid_until), (1000, 120));
+		assert!(!offer.all_signed);
+		assert_eq!(offer.prospective_tenant_signatures.into_inner(), vec![(101, true), (102, false)]);
+		assert_ok!(<Balances as fungible::Mutate<_>>::transfer(&102, &2, 1000, Expendable));
+		assert_eq!(ApplicantOffers::<Test>::get(101).unwrap().into_inner(), vec![1]);
+	});
+}
+
+#[test]
+fn co_tenant_can_revoke_signature_or_decline_offer() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		for applicant_id in [101, 102] {
+			assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), applicant_id));
+			let _ = <Balances as fungible::Mutate<_>>::mint_into(&applicant_id, 1000);
+		}
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(101).unwrap();
+		tenants.try_push(102).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(101), 1, 900, 51, 101, tenants, 100, None));
+
+		assert_noop!(RealEstate::revoke_signature(RuntimeOrigin::signed(102), 1), Error::<Test>::OfferNotSigned);
+		assert_ok!(RealEstate::sign_offer(RuntimeOrigin::signed(102), 1));
+		assert_ok!(RealEstate::revoke_signature(RuntimeOrigin::signed(102), 1));
+		assert!(!Offers::<Test>::get(1).unwrap().all_signed);
+		assert_noop!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 1), Error::<Test>::OfferNotFullySigned);
+
+		assert_noop!(RealEstate::decline_offer(RuntimeOrigin::signed(101), 1), Error::<Test>::LeadTenantCannotDecline);
+		assert_ok!(RealEstate::decline_offer(RuntimeOrigin::signed(102), 1));
+		System::assert_last_event(Event::OfferDeclined { offer_id: 1, applicant_id: 102 }.into());
+		assert_eq!(Offers::<Test>::get(1).unwrap().offer_status, OfferStatus::Cancelled);
+		assert_ok!(<Balances as fungible::Mutate<_>>::transfer(&101, &2, 1000, Expendable));
+	});
+}
+
+#[test]
+fn sealed_offers_are_revealed_before_acceptance() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		for applicant_id in [1, 3] {
+			assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), applicant_id));
+			let _ = <Balances as fungible::Mutate<_>>::mint_into(&applicant_id, 1000);
+		}
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Sealed { commit_end: 10, reveal_end: 20 }, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(1).unwrap();
+		assert_noop!(
+			RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants.clone(), 40, None),
+			Error::<Test>::ListingIsSealed
+		);
+
+		let salt = sp_core::H256::repeat_byte(7);
+		let commitment = BlakeTwo256::hash_of(&(1u128, 1u64, 900u32, 51u64, 101u64, salt));
+		assert_ok!(RealEstate::commit_sealed_offer(RuntimeOrigin::signed(1), 1, commitment, 950));
+		assert_ok!(RealEstate::commit_sealed_offer(RuntimeOrigin::signed(3), 1, commitment, 950));
+		assert_noop!(
+			RealEstate::reveal_sealed_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants.clone(), 40, None, salt),
+			Error::<Test>::NotInRevealWindow
+		);
+
+		System::set_block_number(11);
+		assert_noop!(
+			RealEstate::reveal_sealed_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants.clone(), 40, None, sp_core::H256::repeat_byte(8)),
+			Error::<Test>::CommitmentMismatch
+		);
+		assert_ok!(RealEstate::reveal_sealed_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants.clone(), 40, None, salt));
+		// A copied commitment doesn't open for anyone but the applicant who made it
+		let mut copier = BoundedVec::new();
+		copier.try_push(3).unwrap();
+		assert_noop!(
+			RealEstate::reveal_sealed_offer(RuntimeOrigin::signed(3), 1, 900, 51, 101, copier, 40, None, salt),
+			Error::<Test>::CommitmentMismatch
+		);
+		assert_noop!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 1), Error::<Test>::RevealWindowOpen);
+		assert_noop!(RealEstate::propose_counter_offer(RuntimeOrigin::signed(2), 1, 950, 51, 101), Error::<Test>::RevealWindowOpen);
+		// Revealed offers can't be raised or lowered after seeing the other reveals
+		assert_noop!(
+			RealEstate::amend_offer(RuntimeOrigin::signed(1), 1, 2000, 51, 101, tenants, 40, None),
+			Error::<Test>::ListingIsSealed
+		);
+
+		System::set_block_number(21);
+		RealEstate::on_initialize(21);
+		System::assert_last_event(Event::SealedOfferExpired { listing_id: 1, applicant_id: 3 }.into());
+		assert_ok!(<Balances as fungible::Mutate<_>>::transfer(&3, &2, 1000, Expendable));
+		assert_ok!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 1));
+		assert_eq!(Balances::free_balance(&2), 1900);
+	});
+}
+
+#[test]
+fn highest_offer_is_accepted_after_deadline() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		for applicant_id in [1, 3, 4] {
+			assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), applicant_id));
+			let _ = <Balances as fungible::Mutate<_>>::mint_into(&applicant_id, 2000);
+		}
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::HighestAfterDeadline { deadline: 10 }, ListingAttributes::default()));
+		for (applicant_id, offer_price) in [(1, 1100), (3, 1200), (4, 900)] {
+			let mut tenants = BoundedVec::new();
+			tenants.try_push(applicant_id).unwrap();
+			assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(applicant_id), 1, offer_price, 51, 101, tenants, 100, None));
+		}
+
+		System::set_block_number(10);
+		RealEstate::on_initialize(10);
+		assert!(Tenancies::<Test>::get(1).is_none());
+
+		System::set_block_number(11);
+		RealEstate::on_initialize(11);
+		System::assert_has_event(Event::OfferAutoAccepted { listing_id: 1, offer_id: 2 }.into());
+		assert_eq!(Offers::<Test>::get(2).unwrap().offer_status, OfferStatus::Accepted);
+		assert_eq!(Offers::<Test>::get(1).unwrap().offer_status, OfferStatus::Rejected);
+		assert_eq!(Balances::free_balance(&2), 1200);
+	});
+}
+
+#[test]
+fn first_offer_at_asking_price_is_accepted() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		for applicant_id in [1, 3] {
+			assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), applicant_id));
+			let _ = <Balances as fungible::Mutate<_>>::mint_into(&applicant_id, 2000);
+		}
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::FirstAtAsking, ListingAttributes::default()));
+		for (applicant_id, offer_price) in [(1, 900), (3, 1000)] {
+			let mut tenants = BoundedVec::new();
+			tenants.try_push(applicant_id).unwrap();
+			assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(applicant_id), 1, offer_price, 51, 101, tenants, 100, None));
+		}
+
+		System::set_block_number(2);
+		RealEstate::on_initialize(2);
+		System::assert_has_event(Event::OfferAutoAccepted { listing_id: 1, offer_id: 2 }.into());
+		assert_eq!(Tenancies::<Test>::get(1).unwrap().rental_price, 1000);
+	});
+}
+
+#[test]
+fn deposits_are_held_separately_from_rent() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1301);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 900, 50, 100, 200, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(1).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants, 100, None));
+		assert_eq!(Balances::balance_on_hold(&HoldReason::HoldingDeposit.into(), &1), 100);
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SecurityDeposit.into(), &1), 200);
+
+		assert_ok!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 1));
+		assert_eq!(Balances::free_balance(&2), 900);
+		assert_eq!(Balances::balance_on_hold(&HoldReason::HoldingDeposit.into(), &1), 0);
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SecurityDeposit.into(), &1), 200);
+		assert_eq!(Balances::free_balance(&1), 201);
+		let tenancy = Tenancies::<Test>::get(1).unwrap();
+		assert_eq!((tenancy.rental_price, tenancy.holding_deposit, tenancy.security_deposit), (900, 100, 200));
+	});
+}
+
+#[test]
+fn one_balance_cannot_back_overlapping_offers() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		for (applicant_id, balance) in [(101, 1000), (102, 600), (103, 1000)] {
+			assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), applicant_id));
+			let _ = <Balances as fungible::Mutate<_>>::mint_into(&applicant_id, balance);
+		}
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		for lead_tenant in [101, 103] {
+			let mut tenants = BoundedVec::new();
+			tenants.try_push(lead_tenant).unwrap();
+			tenants.try_push(102).unwrap();
+			assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(lead_tenant), 1, 1000, 51, 101, tenants, 100, None));
+		}
+
+		assert_ok!(RealEstate::sign_offer(RuntimeOrigin::signed(102), 1));
+		assert_eq!(Balances::balance_on_hold(&HoldReason::Offer.into(), &102), 500);
+		assert_noop!(RealEstate::sign_offer(RuntimeOrigin::signed(102), 1), Error::<Test>::OfferAlreadySigned);
+		assert_noop!(RealEstate::sign_offer(RuntimeOrigin::signed(102), 2), Error::<Test>::InsufficientFundsForRentShare);
+
+		assert_ok!(RealEstate::revoke_signature(RuntimeOrigin::signed(102), 1));
+		assert_ok!(RealEstate::sign_offer(RuntimeOrigin::signed(102), 2));
+		assert_eq!(Balances::balance_on_hold(&HoldReason::Offer.into(), &102), 500);
+	});
+}
+
+#[test]
+fn settlement_is_rolled_back_when_rent_share_is_not_held() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		for applicant_id in [101, 102] {
+			assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), applicant_id));
+			let _ = <Balances as fungible::Mutate<_>>::mint_into(&applicant_id, 1000);
+		}
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(101).unwrap();
+		tenants.try_push(102).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(101), 1, 900, 51, 101, tenants, 100, None));
+		assert_ok!(RealEstate::sign_offer(RuntimeOrigin::signed(102), 1));
+
+		// The lead tenant's share would go through, but the co-tenant's has gone missing
+		assert_ok!(<Balances as fungible::MutateHold<_>>::release(&HoldReason::Offer.into(), &102, 450, Precision::Exact));
+		assert_noop!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 1), Error::<Test>::RentShareNotHeld);
+		assert_eq!(Offers::<Test>::get(1).unwrap().offer_status, OfferStatus::Pending);
+		assert!(Tenancies::<Test>::get(1).is_none());
+
+		assert_ok!(<Balances as fungible::MutateHold<_>>::hold(&HoldReason::Offer.into(), &102, 450));
+		assert_ok!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 1));
+		System::assert_has_event(Event::RentPaid { offer_id: 1, from: 101, to: 2, amount: 450 }.into());
+		System::assert_has_event(Event::RentPaid { offer_id: 1, from: 102, to: 2, amount: 450 }.into());
+		assert_eq!(Balances::free_balance(&2), 900);
+	});
+}
+
+#[test]
+fn settlement_is_rolled_back_when_landlord_cannot_receive_rent() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1000);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 100, 200, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(1).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 600, 51, 101, tenants, 100, None));
+
+		// Crediting the rent would overflow the landlord's balance
+		assert_ok!(Balances::force_set_balance(RuntimeOrigin::root(), 2, u128::MAX));
+		assert_noop!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 1), Error::<Test>::RentTransferFailed);
+		assert_eq!(Balances::balance_on_hold(&HoldReason::Offer.into(), &1), 600);
+		assert_eq!(Balances::balance_on_hold(&HoldReason::HoldingDeposit.into(), &1), 100);
+
+		assert_ok!(Balances::force_set_balance(RuntimeOrigin::root(), 2, 0));
+		assert_ok!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 1));
+		System::assert_has_event(Event::RentPaid { offer_id: 1, from: 1, to: 2, amount: 600 }.into());
+		System::assert_has_event(Event::HoldingDepositReturned { offer_id: 1, to: 1, amount: 100 }.into());
+		System::assert_has_event(Event::SecurityDepositRetained { offer_id: 1, from: 1, amount: 200 }.into());
+	});
+}
+
+#[test]
+fn landlord_can_update_and_close_listing() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1000);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		assert_eq!(Listings::<Test>::get(1).unwrap().status, ListingStatus::Open);
+
+		assert_noop!(RealEstate::update_listing(RuntimeOrigin::signed(1), 1, Some(900), None), Error::<Test>::Unauthorized);
+		assert_ok!(RealEstate::update_listing(RuntimeOrigin::signed(2), 1, Some(900), None));
+		System::assert_last_event(Event::ListingUpdated { listing_id: 1, rental_price: 900, availability_date: 50 }.into());
+
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(1).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants.clone(), 100, None));
+		assert_eq!(Listings::<Test>::get(1).unwrap().status, ListingStatus::UnderOffer);
+
+		assert_noop!(RealEstate::close_listing(RuntimeOrigin::signed(1), 1), Error::<Test>::Unauthorized);
+		assert_ok!(RealEstate::close_listing(RuntimeOrigin::signed(2), 1));
+		System::assert_has_event(Event::OfferRejected { offer_id: 1, reason_code: None }.into());
+		System::assert_last_event(Event::ListingClosed { listing_id: 1 }.into());
+		assert_eq!(Listings::<Test>::get(1).unwrap().status, ListingStatus::Closed);
+		assert_eq!(Offers::<Test>::get(1).unwrap().offer_status, OfferStatus::Rejected);
+		assert_ok!(<Balances as fungible::Mutate<_>>::transfer(&1, &2, 1000, Expendable));
+
+		assert_noop!(
+			RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants, 100, None),
+			Error::<Test>::ListingNotOpen
+		);
+		assert_noop!(RealEstate::update_listing(RuntimeOrigin::signed(2), 1, None, Some(60)), Error::<Test>::ListingNotOpen);
+	});
+}
+
+#[test]
+fn property_keeps_its_listing_history() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		assert_noop!(
+			RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1100, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()),
+			Error::<Test>::PropertyAlreadyListed
+		);
+
+		assert_ok!(RealEstate::close_listing(RuntimeOrigin::signed(2), 1));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1100, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		System::assert_last_event(Event::NewListingCreated { listing_id: 2, property_id: 1, rental_price: 1100, availability_date: 50, holding_deposit: 0, security_deposit: 0 }.into());
+
+		let history = RealEstate::get_property_listings(1);
+		assert_eq!(history.iter().map(|listing| (listing.listing_id, listing.rental_price)).collect::<Vec<_>>(), vec![(1, 1000), (2, 1100)]);
+		assert_eq!(history[0].status, ListingStatus::Closed);
+		assert_eq!(history[1].status, ListingStatus::Open);
+
+		// Once the property is let it can't be listed again
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 2000);
+		let tenants: BoundedVec<u64, _> = BoundedVec::try_from(vec![1]).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 2, 1100, 51, 101, tenants, 100, None));
+		assert_ok!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 1));
+		assert_noop!(
+			RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1200, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()),
+			Error::<Test>::TenancyAlreadyExists
+		);
+	});
+}
+
+#[test]
+fn offers_must_fit_listing_attributes() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		for applicant_id in [101, 102, 103] {
+			assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), applicant_id));
+			let _ = <Balances as fungible::Mutate<_>>::mint_into(&applicant_id, 1000);
+		}
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		let attributes = ListingAttributes { bedrooms: 2, bathrooms: 1, furnishing: Furnishing::Furnished, bills_included: true, pets_allowed: false, max_occupants: Some(2), min_term: Some(30), max_term: Some(60), viewing_required: false };
+		assert_noop!(
+			RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes { min_term: Some(90), ..attributes.clone() }),
+			Error::<Test>::InvalidListingAttributes
+		);
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, attributes));
+
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(101).unwrap();
+		tenants.try_push(102).unwrap();
+		tenants.try_push(103).unwrap();
+		assert_noop!(
+			RealEstate::submit_offer(RuntimeOrigin::signed(101), 1, 1000, 51, 101, tenants.clone(), 100, None),
+			Error::<Test>::TooManyOccupants
+		);
+		tenants.pop();
+		assert_noop!(
+			RealEstate::submit_offer(RuntimeOrigin::signed(101), 1, 1000, 51, 71, tenants.clone(), 100, None),
+			Error::<Test>::TermTooShort
+		);
+		assert_noop!(
+			RealEstate::submit_offer(RuntimeOrigin::signed(101), 1, 1000, 51, 121, tenants.clone(), 100, None),
+			Error::<Test>::TermTooLong
+		);
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(101), 1, 1000, 51, 101, tenants, 100, None));
+
+		let matching = ListingFilter { min_bedrooms: Some(2), furnishing: Some(Furnishing::Furnished), occupants: Some(2), ..Default::default() };
+		assert_eq!(RealEstate::find_listings(matching).len(), 1);
+		assert!(RealEstate::find_listings(ListingFilter { pets_allowed: Some(true), ..Default::default() }).is_empty());
+		assert!(RealEstate::find_listings(ListingFilter { max_rental_price: Some(900), ..Default::default() }).is_empty());
+	});
+}
+
+#[test]
+fn storage_deposits_are_held_until_cleanup() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		ListingDeposit::set(10);
+		OfferDeposit::set(5);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1000);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_noop!(
+			RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()),
+			Error::<Test>::InsufficientFundsForStorageDeposit
+		);
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&2, 100);
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		assert_eq!(Balances::balance_on_hold(&HoldReason::ListingDeposit.into(), &2), 10);
+
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(1).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants, 100, None));
+		assert_eq!(Balances::balance_on_hold(&HoldReason::OfferDeposit.into(), &1), 5);
+		assert_ok!(RealEstate::withdraw_offer(RuntimeOrigin::signed(1), 1));
+		assert_eq!(Balances::balance_on_hold(&HoldReason::OfferDeposit.into(), &1), 0);
+
+		assert_ok!(RealEstate::close_listing(RuntimeOrigin::signed(2), 1));
+		assert_eq!(Balances::balance_on_hold(&HoldReason::ListingDeposit.into(), &2), 0);
+		assert_eq!(Balances::free_balance(&1), 1000);
+		assert_eq!(Balances::free_balance(&2), 100);
+	});
+}
+
+#[test]
+fn offer_requires_attended_viewing_when_listing_asks_for_one() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		for applicant_id in [1, 3] {
+			assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), applicant_id));
+			let _ = <Balances as fungible::Mutate<_>>::mint_into(&applicant_id, 1000);
+		}
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		let attributes = ListingAttributes { viewing_required: true, ..Default::default() };
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, attributes));
+		let slots = BoundedVec::try_from(vec![(10, 12), (20, 22)]).unwrap();
+		assert_noop!(RealEstate::publish_viewing_slots(RuntimeOrigin::signed(1), 1, slots.clone()), Error::<Test>::Unauthorized);
+		assert_ok!(RealEstate::publish_viewing_slots(RuntimeOrigin::signed(2), 1, slots));
+
+		assert_ok!(RealEstate::book_viewing(RuntimeOrigin::signed(1), 1, 0));
+		assert_noop!(RealEstate::book_viewing(RuntimeOrigin::signed(3), 1, 0), Error::<Test>::ViewingSlotAlreadyBooked);
+		assert_noop!(RealEstate::book_viewing(RuntimeOrigin::signed(1), 1, 1), Error::<Test>::ViewingAlreadyBooked);
+		assert_ok!(RealEstate::book_viewing(RuntimeOrigin::signed(3), 1, 1));
+		assert_noop!(RealEstate::cancel_viewing(RuntimeOrigin::signed(1), 1, 1), Error::<Test>::Unauthorized);
+		assert_ok!(RealEstate::cancel_viewing(RuntimeOrigin::signed(3), 1, 1));
+		System::assert_last_event(Event::ViewingCancelled { listing_id: 1, slot_index: 1, applicant_id: 3 }.into());
+		assert!(ViewingSlots::<Test>::get(1).unwrap()[1].booked_by.is_none());
+
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(1).unwrap();
+		assert_noop!(
+			RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 1000, 51, 101, tenants.clone(), 100, None),
+			Error::<Test>::ViewingNotAttended
+		);
+		assert_noop!(RealEstate::mark_viewing_attended(RuntimeOrigin::signed(2), 1, 0), Error::<Test>::ViewingNotStarted);
+		System::set_block_number(10);
+		assert_ok!(RealEstate::mark_viewing_attended(RuntimeOrigin::signed(2), 1, 0));
+		System::assert_last_event(Event::ViewingAttended { listing_id: 1, slot_index: 0, applicant_id: 1 }.into());
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 1000, 51, 101, tenants, 100, None));
+	});
+}
+
+#[test]
+fn auction_is_settled_with_the_highest_bid() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		for applicant_id in [1, 3] {
+			assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), applicant_id));
+			let _ = <Balances as fungible::Mutate<_>>::mint_into(&applicant_id, 2000);
+		}
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Auction { reserve_price: 1000, end: 10 }, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants_1 = BoundedVec::new();
+		tenants_1.try_push(1).unwrap();
+		let mut tenants_3 = BoundedVec::new();
+		tenants_3.try_push(3).unwrap();
+		assert_noop!(
+			RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 1000, 51, 101, tenants_1.clone(), 100, None),
+			Error::<Test>::ListingIsAuction
+		);
+		assert_noop!(RealEstate::place_bid(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants_1.clone(), None), Error::<Test>::BidBelowReserve);
+		assert_ok!(RealEstate::place_bid(RuntimeOrigin::signed(1), 1, 1000, 51, 101, tenants_1.clone(), None));
+		assert_noop!(RealEstate::place_bid(RuntimeOrigin::signed(3), 1, 1000, 51, 101, tenants_3.clone(), None), Error::<Test>::BidTooLow);
+		assert_ok!(RealEstate::place_bid(RuntimeOrigin::signed(3), 1, 1100, 51, 101, tenants_3, None));
+		System::assert_has_event(Event::Outbid { listing_id: 1, offer_id: 1 }.into());
+		assert_eq!(Balances::balance_on_hold(&HoldReason::Offer.into(), &1), 0);
+		assert_ok!(RealEstate::place_bid(RuntimeOrigin::signed(1), 1, 1200, 51, 101, tenants_1, None));
+		assert_eq!(Offers::<Test>::get(2).unwrap().offer_status, OfferStatus::Rejected);
+		assert_noop!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 3), Error::<Test>::AuctionNotEnded);
+
+		System::set_block_number(11);
+		RealEstate::on_initialize(11);
+		System::assert_has_event(Event::AuctionSettled { listing_id: 1, offer_id: 3 }.into());
+		assert_eq!(Tenancies::<Test>::get(1).unwrap().rental_price, 1200);
+		assert_eq!(Balances::free_balance(&2), 1200);
+		assert_eq!(Balances::free_balance(&3), 2000);
+	});
+}
+
+#[test]
+fn agents_act_within_their_permissions() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1000);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		let listing_agent = AgentPermissions { manage_listings: true, ..Default::default() };
+		assert_noop!(RealEstate::assign_agent(RuntimeOrigin::signed(5), 1, 5, listing_agent, Permill::zero()), Error::<Test>::Unauthorized);
+		assert_ok!(RealEstate::assign_agent(RuntimeOrigin::signed(2), 1, 5, listing_agent, Permill::zero()));
+		System::assert_last_event(Event::AgentAssigned { property_id: 1, agent_id: 5, permissions: listing_agent, commission: Permill::zero() }.into());
+
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(5), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		assert_eq!(Listings::<Test>::get(1).unwrap().lister, 5);
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(1).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 1000, 51, 101, tenants, 100, None));
+		assert_noop!(RealEstate::accept_offer(RuntimeOrigin::signed(5), 1), Error::<Test>::Unauthorized);
+
+		assert_ok!(RealEstate::assign_agent(RuntimeOrigin::signed(2), 1, 5, AgentPermissions { manage_listings: true, manage_offers: true, manage_tenancy: false }, Permill::zero()));
+		assert_eq!(RealEstate::get_property(1).unwrap().assigned_agents.len(), 1);
+		assert_ok!(RealEstate::accept_offer(RuntimeOrigin::signed(5), 1));
+		assert_eq!(Balances::free_balance(&2), 1000);
+
+		assert_ok!(RealEstate::remove_agent(RuntimeOrigin::signed(2), 1, 5));
+		assert_noop!(RealEstate::remove_agent(RuntimeOrigin::signed(2), 1, 5), Error::<Test>::AgentNotAssigned);
+		assert_noop!(
+			RealEstate::create_listing(RuntimeOrigin::signed(5), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()),
+			Error::<Test>::Unauthorized
+		);
+	});
+}
+
+#[test]
+fn listing_agent_takes_commission_on_acceptance() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		for applicant_id in [101, 102] {
+			assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), applicant_id));
+			let _ = <Balances as fungible::Mutate<_>>::mint_into(&applicant_id, 1000);
+		}
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		let listing_agent = AgentPermissions { manage_listings: true, ..Default::default() };
+		assert_ok!(RealEstate::assign_agent(RuntimeOrigin::signed(2), 1, 5, listing_agent, Permill::from_percent(10)));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(5), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(101).unwrap();
+		tenants.try_push(102).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(101), 1, 1000, 51, 101, tenants, 100, None));
+		assert_ok!(RealEstate::sign_offer(RuntimeOrigin::signed(102), 1));
+		assert_ok!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 1));
+
+		for tenant_id in [101, 102] {
+			System::assert_has_event(Event::CommissionPaid { offer_id: 1, from: tenant_id, to: 5, amount: 50 }.into());
+			System::assert_has_event(Event::RentPaid { offer_id: 1, from: tenant_id, to: 2, amount: 450 }.into());
+		}
+		assert_eq!(Balances::free_balance(&5), 100);
+		assert_eq!(Balances::free_balance(&2), 900);
+	});
+}
+
+#[test]
+fn property_can_only_be_registered_once() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_noop!(
+			RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 3),
+			Error::<Test>::PropertyAlreadyRegistered
+		);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(2), 3));
+
+		let property = RealEstate::get_property_by_address(sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(2)).unwrap();
+		assert_eq!((property.property_id, property.landlord_id), (2, 3));
+		assert_eq!(RealEstate::get_property_by_address(sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1)).unwrap().landlord_id, 2);
+		assert!(RealEstate::get_property_by_address(sp_core::H256::repeat_byte(2), sp_core::H256::repeat_byte(1)).is_none());
+	});
+}
+
+#[test]
+fn registrar_approves_or_rejects_property_registrations() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		let evidence = sp_core::H256::repeat_byte(9);
+		assert_noop!(
+			RealEstate::request_property_registration(RuntimeOrigin::signed(2), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), evidence),
+			Error::<Test>::Unauthorized
+		);
+		assert_ok!(RealEstate::register_landlord(RuntimeOrigin::root(), 2));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&2, 1000);
+		assert_ok!(RealEstate::request_property_registration(RuntimeOrigin::signed(2), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), evidence));
+		assert_noop!(
+			RealEstate::request_property_registration(RuntimeOrigin::signed(2), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), evidence),
+			Error::<Test>::RegistrationAlreadyRequested
+		);
+		assert_ok!(RealEstate::request_property_registration(RuntimeOrigin::signed(2), sp_core::H256::repeat_byte(2), sp_core::H256::repeat_byte(2), evidence));
+		assert_eq!(RealEstate::get_pending_registrations().iter().map(|request| request.request_id).collect::<Vec<_>>(), vec![1, 2]);
+		assert_eq!(Balances::balance_on_hold(&HoldReason::RegistrationDeposit.into(), &2), 100);
+
+		assert_noop!(RealEstate::approve_property_registration(RuntimeOrigin::signed(2), 1), DispatchError::BadOrigin);
+		assert_ok!(RealEstate::approve_property_registration(RuntimeOrigin::root(), 1));
+		System::assert_last_event(Event::PropertyRegistrationApproved { request_id: 1, property_id: 1 }.into());
+		assert_eq!(RealEstate::get_property(1).unwrap().landlord_id, 2);
+		assert_eq!(Balances::free_balance(&2), 900);
+
+		assert_ok!(RealEstate::reject_property_registration(RuntimeOrigin::root(), 2, true));
+		System::assert_last_event(Event::PropertyRegistrationRejected { request_id: 2, deposit_slashed: true }.into());
+		assert!(RealEstate::get_pending_registrations().is_empty());
+		assert_eq!(Balances::free_balance(&2), 900);
+		assert_eq!(<Balances as fungible::Inspect<_>>::total_balance(&2), 900);
+		assert_noop!(RealEstate::approve_property_registration(RuntimeOrigin::root(), 2), Error::<Test>::RegistrationRequestDoesNotExist);
+	});
+}
+
+#[test]
+fn closed_offers_are_taken_off_the_expiry_schedule() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 3));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1000);
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&3, 1000);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let tenants: BoundedVec<u64, _> = BoundedVec::try_from(vec![1]).unwrap();
+		// More offers than MaxOffersExpiringPerBlock, all expiring in the same block
+		for offer_id in 1..=25 {
+			assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants.clone(), 40, None));
+			assert_ok!(RealEstate::withdraw_offer(RuntimeOrigin::signed(1), offer_id));
+		}
+		assert!(OfferExpiries::<Test>::get(41).unwrap().is_empty());
+
+		let tenants: BoundedVec<u64, _> = BoundedVec::try_from(vec![3]).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(3), 1, 900, 51, 101, tenants, 40, None));
+		assert_ok!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 26));
+		assert!(OfferExpiries::<Test>::get(41).unwrap().is_empty());
+	});
+}
+
+#[test]
+fn tenants_can_only_be_on_an_offer_once() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 3));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1000);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let tenants: BoundedVec<u64, _> = BoundedVec::try_from(vec![1, 3, 3]).unwrap();
+		assert_noop!(
+			RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants, 100, None),
+			Error::<Test>::DuplicateTenantIds
+		);
+
+		let tenants: BoundedVec<u64, _> = BoundedVec::try_from(vec![1, 3]).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants, 100, None));
+		let tenants: BoundedVec<u64, _> = BoundedVec::try_from(vec![1, 1]).unwrap();
+		assert_noop!(
+			RealEstate::amend_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants, 100, None),
+			Error::<Test>::DuplicateTenantIds
+		);
+	});
+}
+
+#[test]
+fn highest_signed_offer_is_accepted_when_highest_offer_is_unsigned() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		for applicant_id in [1, 3, 4, 5] {
+			assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), applicant_id));
+			let _ = <Balances as fungible::Mutate<_>>::mint_into(&applicant_id, 2000);
+		}
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(2), sp_core::H256::repeat_byte(2), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::HighestAfterDeadline { deadline: 10 }, ListingAttributes::default()));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 2, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::HighestAfterDeadline { deadline: 10 }, ListingAttributes::default()));
+		// 4 never signs the highest offer
+		let tenants: BoundedVec<u64, _> = BoundedVec::try_from(vec![3, 4]).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(3), 1, 1300, 51, 101, tenants.clone(), 100, None));
+		let tenants_1: BoundedVec<u64, _> = BoundedVec::try_from(vec![1]).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 1100, 51, 101, tenants_1, 100, None));
+		// The only offer on the second listing is never fully signed
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(3), 2, 1200, 51, 101, tenants, 100, None));
+
+		System::set_block_number(11);
+		RealEstate::on_initialize(11);
+		System::assert_has_event(Event::OfferAutoAccepted { listing_id: 1, offer_id: 2 }.into());
+		System::assert_has_event(Event::NoOfferAutoAccepted { listing_id: 2 }.into());
+		assert_eq!(Offers::<Test>::get(1).unwrap().offer_status, OfferStatus::Rejected);
+		assert_eq!(Offers::<Test>::get(3).unwrap().offer_status, OfferStatus::Pending);
+		assert_eq!(Balances::free_balance(&2), 1100);
+	});
+}
+
+#[test]
+fn holds_are_shared_across_offers_and_listings() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		ListingDeposit::set(10);
+		OfferDeposit::set(5);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 10_000);
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&2, 1000);
+		// More listings than the balances pallet's MaxHolds
+		for property in 1..=12u8 {
+			assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(property), sp_core::H256::repeat_byte(property), 2));
+			assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), property as u128, 500, 50, 100, 200, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		}
+		assert_eq!(Balances::balance_on_hold(&HoldReason::ListingDeposit.into(), &2), 120);
+
+		// Every offer holds rent, both deposits and the offer deposit
+		let tenants: BoundedVec<u64, _> = BoundedVec::try_from(vec![1]).unwrap();
+		for listing_id in 1..=5 {
+			assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), listing_id, 500, 51, 101, tenants.clone(), 100, None));
+		}
+		assert_eq!(Balances::balance_on_hold(&HoldReason::Offer.into(), &1), 2500);
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SecurityDeposit.into(), &1), 1000);
+		assert_eq!(Balances::balance_on_hold(&HoldReason::OfferDeposit.into(), &1), 25);
+
+		// Closing one offer only releases what that offer held
+		assert_ok!(RealEstate::withdraw_offer(RuntimeOrigin::signed(1), 1));
+		assert_eq!(Balances::balance_on_hold(&HoldReason::Offer.into(), &1), 2000);
+		assert_eq!(Balances::balance_on_hold(&HoldReason::HoldingDeposit.into(), &1), 400);
+		assert_eq!(Balances::balance_on_hold(&HoldReason::OfferDeposit.into(), &1), 20);
+		assert_ok!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 2));
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SecurityDeposit.into(), &1), 800);
+		assert_eq!(Balances::balance_on_hold(&HoldReason::ListingDeposit.into(), &2), 110);
+	});
+}
+
+#[test]
+fn ended_viewing_slots_make_way_for_new_ones() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 3));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1000);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		let attributes = ListingAttributes { viewing_required: true, ..Default::default() };
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Sealed { commit_end: 60, reveal_end: 70 }, AcceptancePolicy::Manual, attributes));
+		let salt = sp_core::H256::repeat_byte(7);
+		let commitment = BlakeTwo256::hash_of(&(1u128, 1u64, 1000u32, 80u64, 180u64, salt));
+		assert_noop!(RealEstate::commit_sealed_offer(RuntimeOrigin::signed(1), 1, commitment, 900), Error::<Test>::ViewingNotAttended);
+
+		// Fill every slot the listing can have
+		let slots = BoundedVec::try_from((0..10).map(|i| (2 + i * 2, 3 + i * 2)).collect::<Vec<_>>()).unwrap();
+		assert_ok!(RealEstate::publish_viewing_slots(RuntimeOrigin::signed(2), 1, slots));
+		assert_ok!(RealEstate::book_viewing(RuntimeOrigin::signed(1), 1, 9));
+		assert_ok!(RealEstate::book_viewing(RuntimeOrigin::signed(3), 1, 0));
+		assert_noop!(
+			RealEstate::publish_viewing_slots(RuntimeOrigin::signed(2), 1, BoundedVec::try_from(vec![(30, 31)]).unwrap()),
+			Error::<Test>::TooManyViewingSlots
+		);
+
+		// Once the first slots have ended they are replaced, booked slots keep their index until attendance is marked
+		System::set_block_number(20);
+		assert_ok!(RealEstate::mark_viewing_attended(RuntimeOrigin::signed(2), 1, 9));
+		assert_ok!(RealEstate::publish_viewing_slots(RuntimeOrigin::signed(2), 1, BoundedVec::try_from(vec![(30, 31), (32, 33)]).unwrap()));
+		let viewing_slots = ViewingSlots::<Test>::get(1).unwrap();
+		assert_eq!(viewing_slots.len(), 10);
+		assert_eq!((viewing_slots[0].booked_by, viewing_slots[0].start), (Some(3), 2));
+		assert_eq!((viewing_slots[1].start, viewing_slots[2].start), (30, 32));
+		assert_eq!(viewing_slots[9].booked_by, Some(1));
+		assert_ok!(RealEstate::commit_sealed_offer(RuntimeOrigin::signed(1), 1, commitment, 900));
+	});
+}
+
+#[test]
+fn leading_bid_is_binding() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		for applicant_id in [1, 3, 4] {
+			assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), applicant_id));
+			let _ = <Balances as fungible::Mutate<_>>::mint_into(&applicant_id, 5000);
+		}
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Auction { reserve_price: 1000, end: 10 }, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let joint_tenants: BoundedVec<u64, _> = BoundedVec::try_from(vec![3, 4]).unwrap();
+		assert_noop!(RealEstate::place_bid(RuntimeOrigin::signed(3), 1, 2000, 51, 101, joint_tenants, None), Error::<Test>::BidNotFullySigned);
+
+		// Outbid bids leave the expiry schedule, so raising a bid any number of times doesn't fill it
+		let tenants_1: BoundedVec<u64, _> = BoundedVec::try_from(vec![1]).unwrap();
+		for bid_price in 1000..1025 {
+			assert_ok!(RealEstate::place_bid(RuntimeOrigin::signed(1), 1, bid_price, 51, 101, tenants_1.clone(), None));
+		}
+		assert_noop!(RealEstate::withdraw_offer(RuntimeOrigin::signed(1), 25), Error::<Test>::LeadingBidIsBinding);
+		let tenants_3: BoundedVec<u64, _> = BoundedVec::try_from(vec![3]).unwrap();
+		assert_ok!(RealEstate::place_bid(RuntimeOrigin::signed(3), 1, 1100, 51, 101, tenants_3, None));
+		assert_eq!(OfferExpiries::<Test>::get(12).unwrap().into_inner(), vec![26]);
+
+		System::set_block_number(11);
+		RealEstate::on_initialize(11);
+		System::assert_has_event(Event::AuctionSettled { listing_id: 1, offer_id: 26 }.into());
+		assert_eq!(Balances::free_balance(&1), 5000);
+	});
+}
+
+#[test]
+fn agents_cannot_offer_on_properties_they_manage() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		for applicant_id in [1, 5] {
+			assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), applicant_id));
+			let _ = <Balances as fungible::Mutate<_>>::mint_into(&applicant_id, 1000);
+		}
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		let offers_agent = AgentPermissions { manage_offers: true, ..Default::default() };
+		assert_ok!(RealEstate::assign_agent(RuntimeOrigin::signed(2), 1, 5, offers_agent, Permill::zero()));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+
+		let tenants: BoundedVec<u64, _> = BoundedVec::try_from(vec![5]).unwrap();
+		assert_noop!(
+			RealEstate::submit_offer(RuntimeOrigin::signed(5), 1, 900, 51, 101, tenants, 100, None),
+			Error::<Test>::LandlordCannotOfferOnOwnProperty
+		);
+		let tenants: BoundedVec<u64, _> = BoundedVec::try_from(vec![1, 5]).unwrap();
+		assert_noop!(
+			RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants, 100, None),
+			Error::<Test>::LandlordCannotOfferOnOwnProperty
+		);
+	});
+}
+
+#[test]
+fn landlord_settles_security_deposit_when_tenancy_ends() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1000);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 100, 200, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(1).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 600, 51, 101, tenants, 100, None));
+		assert_ok!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 1));
+		assert_noop!(RealEstate::end_tenancy(RuntimeOrigin::signed(2), 1, 0), Error::<Test>::TenancyNotEnded);
+
+		System::set_block_number(101);
+		assert_noop!(RealEstate::end_tenancy(RuntimeOrigin::signed(3), 1, 0), Error::<Test>::Unauthorized);
+		assert_noop!(RealEstate::end_tenancy(RuntimeOrigin::signed(1), 1, 0), Error::<Test>::DepositReturnPeriodNotOver);
+		assert_noop!(RealEstate::end_tenancy(RuntimeOrigin::signed(2), 1, 201), Error::<Test>::RetainedDepositTooHigh);
+		// Agents can only settle the deposit when they manage the tenancy, the retained part still goes to the landlord
+		assert_ok!(RealEstate::assign_agent(RuntimeOrigin::signed(2), 1, 5, AgentPermissions { manage_listings: true, manage_offers: true, manage_tenancy: false }, Permill::zero()));
+		assert_ok!(RealEstate::assign_agent(RuntimeOrigin::signed(2), 1, 6, AgentPermissions { manage_listings: false, manage_offers: false, manage_tenancy: true }, Permill::zero()));
+		assert_noop!(RealEstate::end_tenancy(RuntimeOrigin::signed(5), 1, 50), Error::<Test>::Unauthorized);
+		assert_ok!(RealEstate::end_tenancy(RuntimeOrigin::signed(6), 1, 50));
+		System::assert_last_event(Event::TenancyEnded { property_id: 1, offer_id: 1, deposit_returned: 150, deposit_retained: 50 }.into());
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SecurityDeposit.into(), &1), 0);
+		assert_eq!(Balances::free_balance(&1), 350);
+		assert_eq!(Balances::free_balance(&2), 650);
+		assert!(!Tenancies::<Test>::contains_key(1));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 150, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+	});
+}
+
+#[test]
+fn lead_tenant_reclaims_security_deposit_after_return_period() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1000);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 200, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(1).unwrap();
+		assert_ok!(RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 600, 51, 101, tenants, 100, None));
+		assert_ok!(RealEstate::accept_offer(RuntimeOrigin::signed(2), 1));
+
+		System::set_block_number(111);
+		assert_noop!(RealEstate::end_tenancy(RuntimeOrigin::signed(1), 1, 50), Error::<Test>::RetainedDepositTooHigh);
+		assert_ok!(RealEstate::end_tenancy(RuntimeOrigin::signed(1), 1, 0));
+		System::assert_last_event(Event::TenancyEnded { property_id: 1, offer_id: 1, deposit_returned: 200, deposit_retained: 0 }.into());
+		assert_eq!(Balances::free_balance(&1), 400);
+		assert_noop!(RealEstate::end_tenancy(RuntimeOrigin::signed(2), 1, 0), Error::<Test>::TenancyDoesNotExist);
+	});
+}
+
+// Landlord can also be an applicant, but they should not be able to offer on their own property.
+#[test]
+fn landlord_cannot_offer_on_own_property() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 2));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&2, 1000);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(2).unwrap();
+		assert_noop!(
+			RealEstate::submit_offer(RuntimeOrigin::signed(2), 1, 900, 51, 101, tenants, 100, None),
+			Error::<Test>::LandlordCannotOfferOnOwnProperty
+		);
+	});
+}
+
+#[test]
+fn landlord_cannot_be_a_co_tenant_on_offer_for_own_property() {
+	new_test_ext().execute_with(|| {
+		// Go past genesis block so events get deposited
+		System::set_block_number(1);
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 1));
+		assert_ok!(RealEstate::register_applicant(RuntimeOrigin::root(), 2));
+		let _ = <Balances as fungible::Mutate<_>>::mint_into(&1, 1000);
+		assert_ok!(RealEstate::register_property(RuntimeOrigin::root(), sp_core::H256::repeat_byte(1), sp_core::H256::repeat_byte(1), 2));
+		assert_ok!(RealEstate::create_listing(RuntimeOrigin::signed(2), 1, 1000, 50, 0, 0, OfferMode::Open, AcceptancePolicy::Manual, ListingAttributes::default()));
+		let mut tenants = BoundedVec::new();
+		tenants.try_push(1).unwrap();
+		tenants.try_push(2).unwrap();
+		assert_noop!(
+			RealEstate::submit_offer(RuntimeOrigin::signed(1), 1, 900, 51, 101, tenants, 100, None),
+			Error::<Test>::LandlordCannotOfferOnOwnProperty
+		);
+	});
+}
\ No newline at end of file
@@ -1,12 +1,14 @@
-use crate::{Config};
+use crate::{Config, BalanceOf};
 use codec::{Decode, Encode, MaxEncodedLen};
 use frame_system::pallet_prelude::*;
 use frame_support::pallet_prelude::*;
+use frame_support::sp_runtime::Permill;
 
 pub type PropertyId = u128;
 pub type ListingId = u128;
 pub type OfferId = u128;
 pub type TenancyId = u128;
+pub type RegistrationRequestId = u128;
 
 
 #[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone, Debug, PartialEq)]
@@ -14,7 +16,7 @@ pub type TenancyId = u128;
 pub struct Property<T: Config> {
     pub property_id: PropertyId,
     pub landlord_id: T::AccountId,
-    // pub assigned_agents: BoundedVec<T::AccountId, T::MaxNumberOfAgents>,
+    pub assigned_agents: BoundedVec<AgentAssignment<T>, T::MaxNumberOfAgents>,
     pub address: T::Hash,
     pub postal_code: T::Hash,
 }
@@ -24,22 +26,90 @@ impl<T: Config> Property<T> {
         Property {
             property_id,
             landlord_id,
+            assigned_agents: BoundedVec::new(),
             address,
             postal_code
         }
     }
 
-    pub fn create_listing(self, listing_id: ListingId, rental_price: u32, availability_date: BlockNumberFor<T>, lister: T::AccountId) -> Listing<T> {
+    // The landlord can do anything with their property, agents only what they've been given permission for
+    pub fn is_authorised(&self, who: &T::AccountId, permission: AgentPermission) -> bool {
+        self.landlord_id == *who || self.assigned_agents.iter().any(|assignment| assignment.agent_id == *who && assignment.permissions.allows(permission))
+    }
+
+    // The landlord and the agents assigned to the property, none of them can be a tenant on an offer for it
+    pub fn is_managed_by(&self, who: &T::AccountId) -> bool {
+        self.landlord_id == *who || self.assigned_agents.iter().any(|assignment| assignment.agent_id == *who)
+    }
+
+    pub fn create_listing(self, listing_id: ListingId, rental_price: u32, availability_date: BlockNumberFor<T>, holding_deposit: u32, security_deposit: u32, lister: T::AccountId, deposit: BalanceOf<T>, offer_mode: OfferMode<BlockNumberFor<T>>, acceptance_policy: AcceptancePolicy<BlockNumberFor<T>>, attributes: ListingAttributes<BlockNumberFor<T>>) -> Listing<T> {
         Listing {
             listing_id,
             property_id: self.property_id,
             rental_price, 
             availability_date,
-            lister
+            holding_deposit,
+            security_deposit,
+            lister,
+            deposit,
+            offer_mode,
+            acceptance_policy,
+            status: ListingStatus::Open,
+            attributes,
+        }
+    }
+}
+
+#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone)]
+#[scale_info(skip_type_params(T))]
+pub struct RegistrationRequest<T: Config> {
+    pub request_id: RegistrationRequestId,
+    pub landlord_id: T::AccountId,
+    pub address: T::Hash,
+    pub postal_code: T::Hash,
+    // Hash of the documents proving ownership, e.g. title deeds, which are kept off-chain
+    pub evidence: T::Hash,
+    // Held from the landlord until the registrar decides on the request
+    pub deposit: BalanceOf<T>,
+    pub requested_at: BlockNumberFor<T>,
+}
+
+#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone, Debug, PartialEq)]
+#[scale_info(skip_type_params(T))]
+pub struct AgentAssignment<T: Config> {
+    pub agent_id: T::AccountId,
+    pub permissions: AgentPermissions,
+    // Share of the first payment the agent takes on offers for listings they created
+    pub commission: Permill,
+}
+
+#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct AgentPermissions {
+    // create_listing, update_listing, close_listing and viewings
+    pub manage_listings: bool,
+    // accept_offer, reject_offer and counter offers
+    pub manage_offers: bool,
+    // Looking after the tenancy once an offer has been accepted
+    pub manage_tenancy: bool,
+}
+
+impl AgentPermissions {
+    pub fn allows(&self, permission: AgentPermission) -> bool {
+        match permission {
+            AgentPermission::ManageListings => self.manage_listings,
+            AgentPermission::ManageOffers => self.manage_offers,
+            AgentPermission::ManageTenancy => self.manage_tenancy,
         }
     }
 }
 
+#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AgentPermission {
+    ManageListings,
+    ManageOffers,
+    ManageTenancy,
+}
+
 #[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone)]
 #[scale_info(skip_type_params(T))]
 pub struct Listing<T: Config> {
@@ -47,27 +117,169 @@ pub struct Listing<T: Config> {
     pub property_id: PropertyId,
     pub rental_price: u32,
     pub availability_date: BlockNumberFor<T>,
-    pub lister: T::AccountId
+    // Taken from the lead tenant when they make an offer, returned once the offer is settled or closed
+    pub holding_deposit: u32,
+    // Taken from the lead tenant when they make an offer, kept on hold for the length of the tenancy
+    pub security_deposit: u32,
+    // The landlord or the agent who created the listing, they paid the listing deposit
+    pub lister: T::AccountId,
+    // The listing deposit held from the lister while the listing is on the market
+    pub deposit: BalanceOf<T>,
+    pub offer_mode: OfferMode<BlockNumberFor<T>>,
+    pub acceptance_policy: AcceptancePolicy<BlockNumberFor<T>>,
+    pub status: ListingStatus,
+    pub attributes: ListingAttributes<BlockNumberFor<T>>,
+}
+
+impl<T: Config> Listing<T> {
+    // Open and UnderOffer listings are still on the market and take new offers
+    pub fn is_open(&self) -> bool {
+        matches!(self.status, ListingStatus::Open | ListingStatus::UnderOffer)
+    }
+}
+
+#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone, Debug, PartialEq, Eq, Default)]
+pub struct ListingAttributes<BlockNumber> {
+    pub bedrooms: u8,
+    pub bathrooms: u8,
+    pub furnishing: Furnishing,
+    pub bills_included: bool,
+    pub pets_allowed: bool,
+    // Upper bound on the number of tenants on an offer, None for no limit
+    pub max_occupants: Option<u32>,
+    // Bounds on offer_end_date - offer_start_date, in blocks
+    pub min_term: Option<BlockNumber>,
+    pub max_term: Option<BlockNumber>,
+    // The lead tenant has to have attended a viewing of the listing before they can make an offer
+    pub viewing_required: bool,
+}
+
+#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Furnishing {
+    #[default]
+    Unfurnished,
+    PartFurnished,
+    Furnished,
+}
+
+// What an applicant is looking for, every field left as None matches any listing
+#[derive(Encode, Decode, TypeInfo, Clone, Debug, PartialEq, Eq, Default)]
+pub struct ListingFilter {
+    pub max_rental_price: Option<u32>,
+    pub min_bedrooms: Option<u8>,
+    pub min_bathrooms: Option<u8>,
+    pub furnishing: Option<Furnishing>,
+    pub bills_included: Option<bool>,
+    pub pets_allowed: Option<bool>,
+    // Number of people who would live in the property
+    pub occupants: Option<u32>,
+}
+
+impl ListingFilter {
+    pub fn matches<T: Config>(&self, listing: &Listing<T>) -> bool {
+        let attributes = &listing.attributes;
+        self.max_rental_price.map_or(true, |price| listing.rental_price <= price)
+            && self.min_bedrooms.map_or(true, |bedrooms| attributes.bedrooms >= bedrooms)
+            && self.min_bathrooms.map_or(true, |bathrooms| attributes.bathrooms >= bathrooms)
+            && self.furnishing.map_or(true, |furnishing| attributes.furnishing == furnishing)
+            && self.bills_included.map_or(true, |bills_included| attributes.bills_included == bills_included)
+            && self.pets_allowed.map_or(true, |pets_allowed| !pets_allowed || attributes.pets_allowed)
+            && self.occupants.map_or(true, |occupants| attributes.max_occupants.map_or(true, |max| occupants <= max))
+    }
+}
+
+#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone, Debug, PartialEq, Eq)]
+pub enum ListingStatus {
+    // On the market with no pending offers
+    Open,
+    // On the market with at least one pending offer
+    UnderOffer,
+    // An offer was accepted and the property has a tenancy
+    Let,
+    // Taken off the market by the landlord
+    Closed,
+}
+
+#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone, Debug, PartialEq, Eq)]
+pub enum OfferMode<BlockNumber> {
+    // Offers are submitted in the open with submit_offer
+    Open,
+    // Applicants commit to a hidden offer until commit_end and reveal it until reveal_end
+    Sealed { commit_end: BlockNumber, reveal_end: BlockNumber },
+    // Applicants outbid each other with place_bid until end, the highest bid at or above reserve_price wins
+    Auction { reserve_price: u32, end: BlockNumber },
+}
+
+#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone, Debug, PartialEq, Eq)]
+pub enum AcceptancePolicy<BlockNumber> {
+    // The landlord accepts offers themselves with accept_offer
+    Manual,
+    // Once the deadline has passed accept the highest fully signed offer at or above the asking price
+    HighestAfterDeadline { deadline: BlockNumber },
+    // Accept the first offer at or above the asking price as soon as it is fully signed
+    FirstAtAsking,
+}
+
+#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone, Debug, PartialEq)]
+#[scale_info(skip_type_params(T))]
+pub struct ViewingSlot<T: Config> {
+    pub start: BlockNumberFor<T>,
+    pub end: BlockNumberFor<T>,
+    pub booked_by: Option<T::AccountId>,
+    pub attended: bool,
+}
+
+impl<T: Config> ViewingSlot<T> {
+    pub fn new(start: BlockNumberFor<T>, end: BlockNumberFor<T>) -> ViewingSlot<T> {
+        ViewingSlot {
+            start,
+            end,
+            booked_by: None,
+            attended: false,
+        }
+    }
+}
+
+#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone)]
+#[scale_info(skip_type_params(T))]
+pub struct SealedBid<T: Config> {
+    // Hash of (listing_id, applicant_id, offer_price, offer_start_date, offer_end_date, salt),
+    // the applicant is part of it so nobody can copy another applicant's commitment
+    pub commitment: T::Hash,
+    // Held up front, the revealed offer_price can't go above it
+    pub max_price: u32,
+    pub committed_at: BlockNumberFor<T>,
 }
 
 #[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone)]
 #[scale_info(skip_type_params(T))]
 pub struct Tenancy<T: Config> {
     pub property_id: PropertyId,
+    // The offer the tenancy came from, the security deposit stays on hold from its lead tenant until end_tenancy
+    pub offer_id: OfferId,
     pub rental_price: u32,
+    pub holding_deposit: u32,
+    pub security_deposit: u32,
     pub start_date: BlockNumberFor<T>,
     pub end_date: BlockNumberFor<T>,
+    pub lead_tenant: T::AccountId,
     pub tenant_ids: BoundedVec<T::AccountId, T::MaxNumberOfTenants>,
+    pub rent_shares: BoundedVec<(T::AccountId, u32), T::MaxNumberOfTenants>,
 }
 
 impl<T: Config> Tenancy<T> {
     pub fn new(offer: Offer<T>) -> Tenancy<T> {
         Tenancy {
             property_id: offer.property_id,
+            offer_id: offer.offer_id,
             rental_price: offer.offer_price,
+            holding_deposit: offer.holding_deposit,
+            security_deposit: offer.security_deposit,
             start_date: offer.offer_start_date,
             end_date: offer.offer_end_date,
+            lead_tenant: offer.lead_tenant,
             tenant_ids: offer.prospective_tenant_ids,
+            rent_shares: offer.rent_shares,
         }
     }
 }
@@ -76,41 +288,90 @@ impl<T: Config> Tenancy<T> {
 #[scale_info(skip_type_params(T))]
 pub struct Offer<T: Config> {
     pub offer_id: OfferId,
+    pub listing_id: ListingId,
     pub property_id: PropertyId,
+    // The first month's rent, paid to the landlord in advance when the offer is accepted
     pub offer_price: u32,
+    pub holding_deposit: u32,
+    pub security_deposit: u32,
     pub offer_start_date: BlockNumberFor<T>,
     pub offer_end_date: BlockNumberFor<T>,
     pub lead_tenant: T::AccountId,
     pub prospective_tenant_ids: BoundedVec<T::AccountId, T::MaxNumberOfTenants>,
     pub prospective_tenant_signatures: BoundedVec<(T::AccountId, bool), T::MaxNumberOfTenants>,
+    // The part of offer_price each tenant pays, held on the tenant's account once they sign
+    pub rent_shares: BoundedVec<(T::AccountId, u32), T::MaxNumberOfTenants>,
     pub offer_status: OfferStatus,
     pub valid_until: BlockNumberFor<T>,
     pub all_signed: bool,
+    // The offer deposit held from the lead tenant while the offer is pending
+    pub deposit: BalanceOf<T>,
 }
 
 impl<T: Config> Offer<T> {
-    pub fn new(offer_id: OfferId, property_id: PropertyId, offer_price: u32, offer_start_date: BlockNumberFor<T>, offer_end_date: BlockNumberFor<T>, lead_tenant: T::AccountId, prospective_tenant_ids: BoundedVec<T::AccountId, T::MaxNumberOfTenants>, prospective_tenant_signatures:  BoundedVec<(T::AccountId, bool), T::MaxNumberOfTenants>, valid_until: BlockNumberFor<T>, all_signed: bool) -> Offer<T> {
+    pub fn rent_share_of(&self, tenant_id: &T::AccountId) -> Option<u32> {
+        self.rent_shares.iter().find(|(id, _)| id == tenant_id).map(|(_, share)| *share)
+    }
+
+    pub fn new(offer_id: OfferId, listing_id: ListingId, property_id: PropertyId, offer_price: u32, holding_deposit: u32, security_deposit: u32, offer_start_date: BlockNumberFor<T>, offer_end_date: BlockNumberFor<T>, lead_tenant: T::AccountId, prospective_tenant_ids: BoundedVec<T::AccountId, T::MaxNumberOfTenants>, prospective_tenant_signatures:  BoundedVec<(T::AccountId, bool), T::MaxNumberOfTenants>, rent_shares: BoundedVec<(T::AccountId, u32), T::MaxNumberOfTenants>, valid_until: BlockNumberFor<T>, all_signed: bool, deposit: BalanceOf<T>) -> Offer<T> {
 
         Offer {
             offer_id,
+            listing_id,
             property_id,
             offer_price,
+            holding_deposit,
+            security_deposit,
             offer_start_date,
             offer_end_date,
             lead_tenant,
             prospective_tenant_ids,
             prospective_tenant_signatures,
+            rent_shares,
             offer_status: OfferStatus::Pending,
             valid_until,
             all_signed,
+            deposit,
         }
     }
 }
 
-#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone, PartialEq)]
+#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone, Debug, PartialEq)]
 pub enum OfferStatus {
     Cancelled,
     Pending,
     Accepted,
-    Rejected
-}
\ No newline at end of file
+    Rejected,
+    Expired,
+}
+
+#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone)]
+#[scale_info(skip_type_params(T))]
+pub struct CounterOffer<T: Config> {
+    pub offer_price: u32,
+    pub offer_start_date: BlockNumberFor<T>,
+    pub offer_end_date: BlockNumberFor<T>,
+    pub proposed_at: BlockNumberFor<T>,
+    pub status: CounterOfferStatus,
+}
+
+impl<T: Config> CounterOffer<T> {
+    pub fn new(offer_price: u32, offer_start_date: BlockNumberFor<T>, offer_end_date: BlockNumberFor<T>, proposed_at: BlockNumberFor<T>) -> CounterOffer<T> {
+        CounterOffer {
+            offer_price,
+            offer_start_date,
+            offer_end_date,
+            proposed_at,
+            status: CounterOfferStatus::Open,
+        }
+    }
+}
+
+#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone, Debug, PartialEq)]
+pub enum CounterOfferStatus {
+    Open,
+    Accepted,
+    Declined,
+    // The offer was closed while the counter offer was still open
+    Lapsed,
+}